@@ -12,6 +12,8 @@ use serenity::{
     prelude::Context,
 };
 
+use crate::moderation::{self, LogEntry};
+
 use serde::{Deserialize, Serialize};
 
 #[group]
@@ -43,6 +45,7 @@ struct BackupGuildData {
 }
 
 #[command]
+#[only_in(guilds)]
 pub async fn backup(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
     let guild = msg.guild(&ctx.cache).unwrap();
 
@@ -89,14 +92,19 @@ pub async fn backup(ctx: &Context, msg: &Message, mut args: Args) -> CommandResu
     let backup_value = serde_json::to_value(&backup_data).unwrap();
     let backup_json = serde_json::to_string_pretty(&backup_value).unwrap();
 
-    
-
-    msg.reply(
-        &ctx.http,
-        format!("sifuder rapa, clonei o backup anterior e fds"),
+    moderation::record(
+        ctx,
+        guild.id,
+        msg.channel_id,
+        LogEntry::new("Backup executado", msg.author.id)
+            .detail("Canais", backup_data.channels.len())
+            .detail("Categorias", backup_data.categories.len()),
     )
-    .await
-    .unwrap();
+    .await;
+
+    msg.reply(&ctx.http, "sifuder rapa, clonei o backup anterior e fds")
+        .await
+        .unwrap();
 
     Ok(())
 }
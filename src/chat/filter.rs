@@ -0,0 +1,250 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+use serenity::{
+    framework::standard::Args,
+    model::{
+        channel::Message,
+        id::{MessageId, UserId},
+    },
+    utils::parse_username,
+};
+
+lazy_static! {
+    static ref LINK: Regex = Regex::new(r"(?i)\b(https?://|www\.|discord\.gg/)\S+").unwrap();
+}
+
+#[derive(Debug)]
+pub enum FilterError {
+    InvalidAmount,
+    UnknownFlag(String),
+    MissingValue(String),
+    InvalidUser,
+    InvalidRegex,
+    InvalidMessageId,
+}
+
+impl std::fmt::Display for FilterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterError::InvalidAmount => {
+                write!(f, "Você deve especificar a quantidade de mensagens.")
+            }
+            FilterError::UnknownFlag(flag) => write!(f, "Filtro desconhecido: `{flag}`."),
+            FilterError::MissingValue(flag) => write!(f, "O filtro `{flag}` precisa de um valor."),
+            FilterError::InvalidUser => write!(f, "Usuário inválido, use uma menção ou um ID."),
+            FilterError::InvalidRegex => write!(f, "Expressão regular inválida."),
+            FilterError::InvalidMessageId => write!(f, "ID de mensagem inválido."),
+        }
+    }
+}
+
+pub enum Criterion {
+    Bots,
+    Contains(String),
+    Regex(Regex),
+    Attachments,
+    Links,
+    Embeds,
+}
+
+impl Criterion {
+    pub fn name(&self) -> String {
+        match self {
+            Criterion::Bots => "--bots".to_string(),
+            Criterion::Contains(text) => format!("--contains \"{text}\""),
+            Criterion::Regex(regex) => format!("--regex `{}`", regex.as_str()),
+            Criterion::Attachments => "--attachments".to_string(),
+            Criterion::Links => "--links".to_string(),
+            Criterion::Embeds => "--embeds".to_string(),
+        }
+    }
+
+    fn matches(&self, message: &Message) -> bool {
+        match self {
+            Criterion::Bots => message.author.bot,
+            Criterion::Contains(text) => message.content.to_lowercase().contains(text),
+            Criterion::Regex(regex) => regex.is_match(&message.content),
+            Criterion::Attachments => !message.attachments.is_empty(),
+            Criterion::Links => LINK.is_match(&message.content),
+            Criterion::Embeds => !message.embeds.is_empty(),
+        }
+    }
+}
+
+// `--user`, `--before` e `--after` restringem a busca; os demais filtros são
+// alternativos e basta a mensagem atender a um deles.
+#[derive(Default)]
+pub struct ClearFilter {
+    pub amount: usize,
+    pub user_id: Option<UserId>,
+    pub before: Option<MessageId>,
+    pub after: Option<MessageId>,
+    pub criteria: Vec<Criterion>,
+}
+
+impl ClearFilter {
+    pub fn parse(mut args: Args) -> Result<Self, FilterError> {
+        let mut filter = ClearFilter {
+            amount: args
+                .single::<usize>()
+                .map_err(|_| FilterError::InvalidAmount)?,
+            ..Default::default()
+        };
+
+        while !args.is_empty() {
+            let flag = args.single::<String>().unwrap_or_default().to_lowercase();
+
+            let criterion = match flag.as_str() {
+                "--user" => {
+                    let user = value(&mut args, &flag)?;
+
+                    filter.user_id = parse_username(&user)
+                        .or_else(|| user.parse::<u64>().ok())
+                        .map(UserId);
+
+                    if filter.user_id.is_none() {
+                        return Err(FilterError::InvalidUser);
+                    }
+
+                    continue;
+                }
+                "--before" => {
+                    filter.before = Some(message_id(&mut args, &flag)?);
+
+                    continue;
+                }
+                "--after" => {
+                    filter.after = Some(message_id(&mut args, &flag)?);
+
+                    continue;
+                }
+                "--bots" => Criterion::Bots,
+                "--contains" => Criterion::Contains(value(&mut args, &flag)?.to_lowercase()),
+                "--regex" => Criterion::Regex(
+                    Regex::new(&value(&mut args, &flag)?).map_err(|_| FilterError::InvalidRegex)?,
+                ),
+                "--attachments" => Criterion::Attachments,
+                "--links" => Criterion::Links,
+                "--embeds" => Criterion::Embeds,
+                _ => return Err(FilterError::UnknownFlag(flag)),
+            };
+
+            filter.criteria.push(criterion);
+        }
+
+        Ok(filter)
+    }
+
+    pub fn matches(&self, message: &Message) -> Option<Vec<usize>> {
+        if let Some(user_id) = self.user_id {
+            if message.author.id != user_id {
+                return None;
+            }
+        }
+
+        if self.criteria.is_empty() {
+            return Some(vec![]);
+        }
+
+        let matched = self
+            .criteria
+            .iter()
+            .enumerate()
+            .filter(|(_, criterion)| criterion.matches(message))
+            .map(|(i, _)| i)
+            .collect::<Vec<usize>>();
+
+        if matched.is_empty() {
+            return None;
+        }
+
+        Some(matched)
+    }
+}
+
+fn value(args: &mut Args, flag: &str) -> Result<String, FilterError> {
+    args.single_quoted::<String>()
+        .ok()
+        .filter(|value| !value.starts_with("--"))
+        .ok_or(FilterError::MissingValue(flag.to_string()))
+}
+
+fn message_id(args: &mut Args, flag: &str) -> Result<MessageId, FilterError> {
+    value(args, flag)?
+        .parse::<u64>()
+        .map(MessageId)
+        .map_err(|_| FilterError::InvalidMessageId)
+}
+
+#[cfg(test)]
+mod tests {
+    use serenity::framework::standard::Delimiter;
+
+    use super::*;
+
+    fn parse(message: &str) -> Result<ClearFilter, FilterError> {
+        ClearFilter::parse(Args::new(message, &[Delimiter::Single(' ')]))
+    }
+
+    #[test]
+    fn parses_amount_and_restrictions() {
+        let filter = parse("50 --user <@!123> --before 10 --after 5").unwrap();
+
+        assert_eq!(filter.amount, 50);
+        assert_eq!(filter.user_id, Some(UserId(123)));
+        assert_eq!(filter.before, Some(MessageId(10)));
+        assert_eq!(filter.after, Some(MessageId(5)));
+        assert!(filter.criteria.is_empty());
+    }
+
+    #[test]
+    fn parses_criteria() {
+        let filter = parse(r#"10 --BOTS --contains "Hello World" --regex ^a+$ --links"#).unwrap();
+
+        let names = filter
+            .criteria
+            .iter()
+            .map(Criterion::name)
+            .collect::<Vec<String>>();
+
+        assert_eq!(
+            names,
+            vec![
+                "--bots",
+                "--contains \"hello world\"",
+                "--regex `^a+$`",
+                "--links"
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_input() {
+        assert!(matches!(parse(""), Err(FilterError::InvalidAmount)));
+        assert!(matches!(parse("abc"), Err(FilterError::InvalidAmount)));
+        assert!(matches!(
+            parse("10 --everything"),
+            Err(FilterError::UnknownFlag(flag)) if flag == "--everything"
+        ));
+        assert!(matches!(
+            parse("10 --contains --bots"),
+            Err(FilterError::MissingValue(_))
+        ));
+        assert!(matches!(
+            parse("10 --user"),
+            Err(FilterError::MissingValue(_))
+        ));
+        assert!(matches!(
+            parse("10 --user bob"),
+            Err(FilterError::InvalidUser)
+        ));
+        assert!(matches!(
+            parse("10 --regex ("),
+            Err(FilterError::InvalidRegex)
+        ));
+        assert!(matches!(
+            parse("10 --before abc"),
+            Err(FilterError::InvalidMessageId)
+        ));
+    }
+}
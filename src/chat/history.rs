@@ -0,0 +1,103 @@
+use serenity::{
+    http::Http,
+    model::{
+        channel::Message,
+        id::{ChannelId, MessageId},
+    },
+};
+
+// Percorre o histórico de um canal de trás para frente, 100 mensagens por vez.
+pub struct History {
+    channel_id: ChannelId,
+    cursor: MessageId,
+    remaining: usize,
+}
+
+impl History {
+    pub fn new(channel_id: ChannelId, before: MessageId, limit: usize) -> Self {
+        History {
+            channel_id,
+            cursor: before,
+            remaining: limit,
+        }
+    }
+
+    pub async fn next_page(&mut self, http: &Http) -> serenity::Result<Vec<Message>> {
+        let limit = match self.page_limit() {
+            Some(limit) => limit,
+            None => return Ok(vec![]),
+        };
+
+        let cursor = self.cursor;
+
+        let messages = self
+            .channel_id
+            .messages(http, |r| r.before(cursor).limit(limit))
+            .await?;
+
+        self.advance(messages.last().map(|last| last.id), messages.len());
+
+        Ok(messages)
+    }
+
+    fn page_limit(&self) -> Option<u64> {
+        match self.remaining {
+            0 => None,
+            remaining => Some(remaining.min(100) as u64),
+        }
+    }
+
+    // Uma página vazia significa que o canal acabou.
+    fn advance(&mut self, last: Option<MessageId>, fetched: usize) {
+        match last {
+            Some(last) => {
+                self.cursor = last;
+                self.remaining = self.remaining.saturating_sub(fetched);
+            }
+            None => self.remaining = 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_until_the_limit() {
+        let mut history = History::new(ChannelId(1), MessageId(1000), 250);
+
+        assert_eq!(history.page_limit(), Some(100));
+
+        history.advance(Some(MessageId(900)), 100);
+        history.advance(Some(MessageId(800)), 100);
+
+        assert_eq!(history.cursor, MessageId(800));
+        assert_eq!(history.page_limit(), Some(50));
+
+        history.advance(Some(MessageId(750)), 50);
+
+        assert_eq!(history.page_limit(), None);
+    }
+
+    #[test]
+    fn stops_at_the_start_of_the_channel() {
+        let mut history = History::new(ChannelId(1), MessageId(1000), 500);
+
+        history.advance(Some(MessageId(970)), 30);
+
+        assert_eq!(history.page_limit(), Some(100));
+
+        history.advance(None, 0);
+
+        assert_eq!(history.page_limit(), None);
+    }
+
+    #[test]
+    fn empty_limit_fetches_nothing() {
+        assert_eq!(
+            History::new(ChannelId(1), MessageId(1), 0).page_limit(),
+            None
+        );
+    }
+}
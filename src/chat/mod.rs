@@ -1,30 +1,101 @@
-use serde_json::json;
+use std::borrow::Cow;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use chrono::Utc;
 use serenity::{
     client::Context,
     framework::standard::{
         macros::{command, group},
         Args, CommandError, CommandResult,
     },
-    model::channel::Message,
+    model::{
+        channel::Message,
+        id::{ChannelId, MessageId},
+        prelude::AttachmentType,
+    },
     prelude::Mentionable,
-    utils::MessageBuilder,
+    utils::{content_safe, parse_channel, ContentSafeOptions, MessageBuilder},
 };
 
+use crate::moderation::{self, LogEntry};
+use crate::scheduler::{self, Job};
+use crate::settings;
+
+use self::filter::ClearFilter;
+use self::history::History;
+use self::transcript::Transcript;
+
+mod filter;
+mod history;
+mod transcript;
+
+pub const CLEAR_SCAN_LIMIT: usize = 500;
+pub const MAX_CLEAR_SCAN_LIMIT: usize = 5000;
+pub const MAX_CLEAR_AMOUNT: usize = 10000;
+const BULK_DELETE_MAX_AGE: i64 = 14 * 24 * 60 * 60 - 60;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(3);
+const MAX_REMINDER: u64 = 365 * 24 * 60 * 60;
+const MAX_REMINDER_LENGTH: usize = 500;
+const TRANSCRIPT_LIMIT: usize = 1000;
+const MAX_TRANSCRIPT_LIMIT: usize = 10000;
+const MAX_UPLOAD_SIZE: usize = 8 * 1024 * 1024;
+const DEFAULT_TRANSCRIPTS_DIR: &str = "data/transcripts";
+
+struct Matched {
+    id: MessageId,
+    created_at: i64,
+    criteria: Vec<usize>,
+    deleted: bool,
+}
+
+struct Progress {
+    message: Option<Message>,
+    last_update: Instant,
+}
+
+impl Progress {
+    async fn update(&mut self, ctx: &Context, content: String, force: bool) {
+        if !force && self.last_update.elapsed() < PROGRESS_INTERVAL {
+            return;
+        }
+
+        self.last_update = Instant::now();
+
+        if let Some(message) = self.message.as_mut() {
+            let _ = message.edit(&ctx.http, |m| m.content(content)).await;
+        }
+    }
+
+    async fn discard(self, ctx: &Context) {
+        if let Some(message) = self.message {
+            let _ = message.delete(&ctx.http).await;
+        }
+    }
+}
+
 #[group]
-#[commands(clear)]
+#[commands(clear, clearscan, remind, transcript, transcripts)]
 pub struct Chat;
 
 #[command]
 #[only_in(guilds)]
-pub async fn clear(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
-    let channel_id = msg.channel_id.0;
+pub async fn clear(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
 
-    let amount = args.parse::<i32>();
+    let filter = match ClearFilter::parse(args) {
+        Ok(filter) => filter,
+        Err(why) => {
+            msg.reply(&ctx.http, why.to_string()).await.unwrap();
 
-    if amount.is_err() {
+            return Err(CommandError::from("Ocorreu um erro."));
+        }
+    };
+
+    if filter.amount < 1 || filter.amount > MAX_CLEAR_AMOUNT {
         msg.reply(
             &ctx.http,
-            "Você deve especificar a quantidade de mensagens.",
+            format!("A quantidade de mensagens deve ser entre 1 e {MAX_CLEAR_AMOUNT}."),
         )
         .await
         .unwrap();
@@ -32,12 +103,78 @@ pub async fn clear(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
         return Err(CommandError::from("Ocorreu um erro."));
     }
 
-    let mut amount = amount.unwrap();
+    let scan_limit = settings::get(guild_id)
+        .await
+        .clear_scan_limit()
+        .max(filter.amount);
+
+    let mut progress = Progress {
+        message: msg
+            .channel_id
+            .say(&ctx.http, "Procurando mensagens...")
+            .await
+            .ok(),
+        last_update: Instant::now(),
+    };
+
+    let mut history = History::new(msg.channel_id, filter.before.unwrap_or(msg.id), scan_limit);
+    let mut scanned = 0;
+    let mut matched: Vec<Matched> = vec![];
+
+    'scan: while matched.len() < filter.amount {
+        let messages = match history.next_page(&ctx.http).await {
+            Ok(messages) if messages.is_empty() => break,
+            Ok(messages) => messages,
+            Err(_) => {
+                progress.discard(ctx).await;
+
+                msg.reply(&ctx.http, "Não foi possível ler as mensagens desse canal.")
+                    .await
+                    .unwrap();
+
+                return Err(CommandError::from("Ocorreu um erro."));
+            }
+        };
+
+        for message in messages {
+            scanned += 1;
+
+            if filter.after.is_some_and(|after| message.id <= after) {
+                break 'scan;
+            }
+
+            if let Some(criteria) = filter.matches(&message) {
+                matched.push(Matched {
+                    id: message.id,
+                    created_at: message.timestamp.unix_timestamp(),
+                    criteria,
+                    deleted: false,
+                });
+
+                if matched.len() >= filter.amount {
+                    break 'scan;
+                }
+            }
+        }
+
+        progress
+            .update(
+                ctx,
+                format!(
+                    "Procurando mensagens... {} encontradas em {scanned} analisadas.",
+                    matched.len()
+                ),
+                false,
+            )
+            .await;
+    }
+
+    if matched.is_empty() {
+        progress.discard(ctx).await;
 
-    if amount < 2 || amount > 100 {
         msg.reply(
             &ctx.http,
-            "A quantidade de mensagens deve ser entre 2 e 100.",
+            format!("Nenhuma mensagem encontrada entre as {scanned} analisadas."),
         )
         .await
         .unwrap();
@@ -45,48 +182,451 @@ pub async fn clear(ctx: &Context, msg: &Message, mut args: Args) -> CommandResul
         return Err(CommandError::from("Ocorreu um erro."));
     }
 
-    amount += 1;
+    delete_matched(ctx, msg.channel_id, &mut matched, &mut progress).await;
 
-    let mut messages_ids = ctx
-        .http
-        .get_messages(
-            msg.channel_id.0,
-            &format!(
-                "?{}",
-                querystring::stringify(vec![("limit", &amount.to_string())])
-            ),
+    let deleted = matched.iter().filter(|m| m.deleted).count();
+    let failed = matched.len() - deleted;
+
+    if deleted == 0 {
+        progress.discard(ctx).await;
+
+        msg.reply(
+            &ctx.http,
+            "Não foi possível deletar as mensagens desse canal.",
         )
         .await
-        .unwrap()
-        .into_iter()
-        .filter(|message| message.id.0 != msg.id.0)
-        .map(|message| format!("{}", message.id.0))
-        .collect::<Vec<String>>();
-
-    if messages_ids.len() == 0 {
-        msg.reply(&ctx.http, "Este canal não possui nenhuma mensagem.")
-            .await
-            .unwrap();
+        .unwrap();
 
         return Err(CommandError::from("Ocorreu um erro."));
     }
 
-    let map = json!({ "messages": messages_ids });
+    let _ = msg.delete(&ctx.http).await;
+
+    let mut counts = vec![0; filter.criteria.len()];
+
+    for matched in matched.iter().filter(|m| m.deleted) {
+        for i in matched.criteria.iter() {
+            counts[*i] += 1;
+        }
+    }
+
+    let mut report = MessageBuilder::new();
+
+    report.push(format!(
+        "{deleted} mensagens apagadas ({scanned} analisadas)."
+    ));
+
+    if failed > 0 {
+        report.push(format!("\n{failed} mensagens não puderam ser apagadas."));
+    }
+
+    if let Some(user_id) = filter.user_id {
+        report.push("\nAutor: ").push(user_id.mention());
+    }
+
+    for (criterion, count) in filter.criteria.iter().zip(counts) {
+        report.push(format!("\n{}: {count}", criterion.name()));
+    }
+
+    let report = report.build();
+
+    match progress.message {
+        Some(_) => progress.update(ctx, report.clone(), true).await,
+        None => {
+            msg.channel_id.say(&ctx.http, &report).await?;
+        }
+    }
+
+    moderation::record(
+        ctx,
+        msg.guild_id.unwrap(),
+        msg.channel_id,
+        LogEntry::new("Mensagens apagadas", msg.author.id)
+            .detail("Canal", msg.channel_id.mention())
+            .detail("Resultado", report),
+    )
+    .await;
+
+    Ok(())
+}
+
+// O Discord só apaga em massa mensagens com menos de 14 dias e recusa o lote
+// inteiro se houver uma mais antiga, então as antigas são apagadas uma a uma.
+async fn delete_matched(
+    ctx: &Context,
+    channel_id: ChannelId,
+    matched: &mut [Matched],
+    progress: &mut Progress,
+) {
+    let total = matched.len();
+    let bulk_limit = Utc::now().timestamp() - BULK_DELETE_MAX_AGE;
+    let mut done = 0;
+
+    let (recent, old): (Vec<usize>, Vec<usize>) =
+        (0..total).partition(|i| matched[*i].created_at > bulk_limit);
+
+    for batch in recent.chunks(100) {
+        let deleted = match batch {
+            [i] => channel_id.delete_message(&ctx.http, matched[*i].id).await,
+            _ => {
+                channel_id
+                    .delete_messages(&ctx.http, batch.iter().map(|i| matched[*i].id))
+                    .await
+            }
+        };
+
+        for i in batch {
+            matched[*i].deleted = deleted.is_ok();
+        }
+
+        done += batch.len();
+
+        progress
+            .update(ctx, format!("Apagando mensagens... {done}/{total}"), false)
+            .await;
+    }
+
+    for i in old {
+        matched[i].deleted = channel_id
+            .delete_message(&ctx.http, matched[i].id)
+            .await
+            .is_ok();
+
+        done += 1;
+
+        progress
+            .update(
+                ctx,
+                format!("Apagando mensagens antigas... {done}/{total}"),
+                false,
+            )
+            .await;
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn clearscan(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let scan_limit = match args.rest().trim().parse::<usize>() {
+        Ok(scan_limit) if (100..=MAX_CLEAR_SCAN_LIMIT).contains(&scan_limit) => scan_limit,
+        _ => {
+            let current = settings::get(guild_id).await.clear_scan_limit();
+
+            msg.reply(
+                &ctx.http,
+                format!(
+                    "O `!clear` analisa até {current} mensagens. Use `!clearscan <100-{MAX_CLEAR_SCAN_LIMIT}>` para mudar."
+                ),
+            )
+            .await?;
 
-    let res = ctx.http.delete_messages(msg.channel_id.0, &map).await;
+            return Ok(());
+        }
+    };
 
-    if res.is_err() {
+    let content = match settings::update(guild_id, |s| s.clear_scan_limit = Some(scan_limit)).await
+    {
+        Ok(_) => format!("O `!clear` vai analisar até {scan_limit} mensagens."),
+        Err(_) => "Não foi possível salvar a configuração.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn remind(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let seconds = humantime::parse_duration(&args.single::<String>().unwrap_or_default())
+        .ok()
+        .map(|duration| duration.as_secs())
+        .filter(|seconds| *seconds > 0 && *seconds <= MAX_REMINDER);
+
+    let content = content_safe(
+        &ctx.cache,
+        args.rest().trim(),
+        &ContentSafeOptions::default(),
+        &msg.mentions,
+    );
+
+    let seconds = match seconds {
+        Some(seconds) if !content.is_empty() && content.chars().count() <= MAX_REMINDER_LENGTH => {
+            seconds
+        }
+        _ => {
+            msg.reply(
+                &ctx.http,
+                format!("Use `!remind <duração> <texto>`, com até 365 dias e {MAX_REMINDER_LENGTH} caracteres."),
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let job = Job::Reminder {
+        user_id: msg.author.id.0,
+        channel_id: msg.channel_id.0,
+        content,
+    };
+
+    let due_at = Utc::now().timestamp() + seconds as i64;
+
+    let scheduled = match scheduler::reminder_id(msg.author.id.0).await {
+        Ok(id) => scheduler::schedule(&id, job, due_at).await,
+        Err(why) => Err(why),
+    };
+
+    let content = match scheduled {
+        Ok(_) => format!("Vou te lembrar <t:{due_at}:R>."),
+        Err(_) => "Não foi possível agendar o lembrete.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn transcript(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let channel_id = match args.current().and_then(parse_channel) {
+        Some(channel_id) => {
+            args.advance();
+
+            ChannelId(channel_id)
+        }
+        None => msg.channel_id,
+    };
+
+    let limit = match args.rest().trim() {
+        "" => TRANSCRIPT_LIMIT,
+        value => match value.parse::<usize>() {
+            Ok(limit) if (1..=MAX_TRANSCRIPT_LIMIT).contains(&limit) => limit,
+            _ => {
+                msg.reply(
+                    &ctx.http,
+                    format!("Use `!transcript [#canal] [1-{MAX_TRANSCRIPT_LIMIT}]`."),
+                )
+                .await?;
+
+                return Ok(());
+            }
+        },
+    };
+
+    let member = msg.member(ctx).await?;
+
+    let channel = ctx.cache.guild(guild_id).and_then(|guild| {
+        let channel = guild
+            .channels
+            .get(&channel_id)
+            .and_then(|channel| channel.clone().guild())
+            .or_else(|| guild.threads.iter().find(|t| t.id == channel_id).cloned())?;
+
+        let permissions = guild.user_permissions_in(&channel, &member).ok()?;
+
+        Some((guild.name.clone(), channel.name, permissions))
+    });
+
+    let (guild_name, channel_name) = match channel {
+        Some((guild_name, channel_name, permissions))
+            if permissions.view_channel() && permissions.read_message_history() =>
+        {
+            (guild_name, channel_name)
+        }
+        Some(_) => {
+            msg.reply(&ctx.http, "Você não pode ler as mensagens desse canal.")
+                .await?;
+
+            return Ok(());
+        }
+        None => {
+            msg.reply(&ctx.http, "Canal não encontrado neste servidor.")
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut progress = Progress {
+        message: msg
+            .channel_id
+            .say(&ctx.http, "Lendo mensagens...")
+            .await
+            .ok(),
+        last_update: Instant::now(),
+    };
+
+    let mut history = History::new(channel_id, msg.id, limit);
+    let mut messages = vec![];
+
+    loop {
+        match history.next_page(&ctx.http).await {
+            Ok(page) if page.is_empty() => break,
+            Ok(page) => messages.extend(page),
+            Err(_) => {
+                progress.discard(ctx).await;
+
+                msg.reply(&ctx.http, "Não foi possível ler as mensagens desse canal.")
+                    .await?;
+
+                return Ok(());
+            }
+        }
+
+        progress
+            .update(
+                ctx,
+                format!("Lendo mensagens... {} lidas.", messages.len()),
+                false,
+            )
+            .await;
+    }
+
+    progress.discard(ctx).await;
+
+    if messages.is_empty() {
+        msg.reply(&ctx.http, "Nenhuma mensagem encontrada nesse canal.")
+            .await?;
+
+        return Ok(());
+    }
+
+    let transcript = Transcript::new(guild_id.0, guild_name, channel_id.0, channel_name, messages);
+
+    let count = transcript.messages.len();
+    let name = format!("transcript-{}-{}", channel_id.0, transcript.generated_at);
+    let html = transcript.to_html();
+    let json = transcript.to_json();
+
+    // Arquivos grandes demais para o Discord vão para o disco mesmo no modo upload.
+    let save = settings::get(guild_id.0).await.save_transcripts
+        || html.len() > MAX_UPLOAD_SIZE
+        || json.len() > MAX_UPLOAD_SIZE;
+
+    let destination = if save {
+        match save_transcript(guild_id.0, &name, &html, &json).await {
+            Ok(path) => format!("salva em `{}`", path.display()),
+            Err(_) => {
+                msg.reply(&ctx.http, "Não foi possível salvar a transcrição.")
+                    .await?;
+
+                return Ok(());
+            }
+        }
+    } else {
+        let files = vec![
+            AttachmentType::Bytes {
+                data: Cow::from(html.into_bytes()),
+                filename: format!("{name}.html"),
+            },
+            AttachmentType::Bytes {
+                data: Cow::from(json.into_bytes()),
+                filename: format!("{name}.json"),
+            },
+        ];
+
+        msg.channel_id
+            .send_message(&ctx.http, |m| {
+                m.content(format!(
+                    "Transcrição de {} com {count} mensagens.",
+                    channel_id.mention()
+                ))
+                .add_files(files)
+            })
+            .await?;
+
+        "enviada no canal".to_string()
+    };
+
+    if save {
         msg.reply(
             &ctx.http,
-            "Não foi possível deletar as mensagens desse canal.",
+            format!(
+                "Transcrição de {} com {count} mensagens {destination}.",
+                channel_id.mention()
+            ),
         )
-        .await
-        .unwrap();
-
-        return Err(CommandError::from("Ocorreu um erro."));
+        .await?;
     }
 
-    msg.delete(&ctx.http).await.unwrap();
+    moderation::record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new("Transcrição gerada", msg.author.id)
+            .detail("Canal", channel_id.mention())
+            .detail("Mensagens", count)
+            .detail("Destino", destination),
+    )
+    .await;
+
+    Ok(())
+}
+
+// Lida ao salvar para permitir apontar para um volume sem recompilar o bot.
+fn transcripts_dir() -> PathBuf {
+    std::env::var("TRANSCRIPTS_DIR")
+        .ok()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(DEFAULT_TRANSCRIPTS_DIR))
+}
+
+async fn save_transcript(
+    guild_id: u64,
+    name: &str,
+    html: &str,
+    json: &str,
+) -> std::io::Result<PathBuf> {
+    let directory = transcripts_dir().join(guild_id.to_string());
+
+    tokio::fs::create_dir_all(&directory).await?;
+    tokio::fs::write(directory.join(format!("{name}.html")), html).await?;
+    tokio::fs::write(directory.join(format!("{name}.json")), json).await?;
+
+    Ok(directory.join(format!("{name}.html")))
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn transcripts(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let save = match args.rest().trim() {
+        "upload" => false,
+        "save" => true,
+        _ => {
+            let current = match settings::get(guild_id).await.save_transcripts {
+                true => "salvas no servidor do bot",
+                false => "enviadas no canal",
+            };
+
+            msg.reply(
+                &ctx.http,
+                format!(
+                    "As transcrições são {current}. Use `!transcripts upload|save` para mudar."
+                ),
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id, |s| s.save_transcripts = save).await {
+        Ok(_) if save => "As transcrições serão salvas no servidor do bot.".to_string(),
+        Ok(_) => "As transcrições serão enviadas no canal.".to_string(),
+        Err(_) => "Não foi possível salvar a configuração.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
 
     Ok(())
 }
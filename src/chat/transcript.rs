@@ -0,0 +1,270 @@
+use chrono::{TimeZone, Utc};
+use serde::Serialize;
+use serenity::model::channel::{Embed, Message};
+
+#[derive(Debug, Serialize)]
+pub struct Transcript {
+    pub guild_id: u64,
+    pub guild_name: String,
+    pub channel_id: u64,
+    pub channel_name: String,
+    pub generated_at: i64,
+    pub messages: Vec<TranscriptMessage>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranscriptAuthor {
+    pub id: u64,
+    pub tag: String,
+    pub avatar_url: String,
+    pub bot: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranscriptAttachment {
+    pub filename: String,
+    pub url: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranscriptMessage {
+    pub id: u64,
+    pub author: TranscriptAuthor,
+    pub content: String,
+    pub created_at: i64,
+    pub edited_at: Option<i64>,
+    pub attachments: Vec<TranscriptAttachment>,
+    pub embeds: Vec<Embed>,
+}
+
+impl From<Message> for TranscriptMessage {
+    fn from(message: Message) -> Self {
+        TranscriptMessage {
+            id: message.id.0,
+            author: TranscriptAuthor {
+                id: message.author.id.0,
+                tag: message.author.tag(),
+                avatar_url: message.author.face(),
+                bot: message.author.bot,
+            },
+            content: message.content,
+            created_at: message.timestamp.unix_timestamp(),
+            edited_at: message.edited_timestamp.map(|t| t.unix_timestamp()),
+            attachments: message
+                .attachments
+                .into_iter()
+                .map(|attachment| TranscriptAttachment {
+                    filename: attachment.filename,
+                    url: attachment.url,
+                    size: attachment.size,
+                })
+                .collect(),
+            embeds: message.embeds,
+        }
+    }
+}
+
+impl Transcript {
+    // As mensagens chegam da mais nova para a mais antiga.
+    pub fn new(
+        guild_id: u64,
+        guild_name: String,
+        channel_id: u64,
+        channel_name: String,
+        messages: Vec<Message>,
+    ) -> Self {
+        Transcript {
+            guild_id,
+            guild_name,
+            channel_id,
+            channel_name,
+            generated_at: Utc::now().timestamp(),
+            messages: messages.into_iter().rev().map(Into::into).collect(),
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    pub fn to_html(&self) -> String {
+        let mut html = String::new();
+
+        html.push_str("<!DOCTYPE html>\n<html lang=\"pt-BR\">\n<head>\n<meta charset=\"utf-8\">\n");
+        html.push_str(&format!(
+            "<title>#{} | {}</title>\n",
+            escape(&self.channel_name),
+            escape(&self.guild_name)
+        ));
+        html.push_str(STYLE);
+        html.push_str("</head>\n<body>\n<header>\n");
+        html.push_str(&format!(
+            "<h1>#{}</h1>\n<p>{} | {} mensagens | gerado em {}</p>\n",
+            escape(&self.channel_name),
+            escape(&self.guild_name),
+            self.messages.len(),
+            format_time(self.generated_at)
+        ));
+        html.push_str("</header>\n<main>\n");
+
+        for message in self.messages.iter() {
+            render_message(&mut html, message);
+        }
+
+        html.push_str("</main>\n</body>\n</html>\n");
+
+        html
+    }
+}
+
+const STYLE: &str = "<style>
+body { margin: 0; background: #313338; color: #dbdee1; font: 15px/1.4 sans-serif; }
+header { padding: 16px 24px; border-bottom: 1px solid #1e1f22; }
+header h1 { margin: 0; font-size: 20px; color: #f2f3f5; }
+header p { margin: 4px 0 0; color: #949ba4; }
+main { padding: 8px 0; }
+.message { display: flex; gap: 16px; padding: 6px 24px; }
+.message:hover { background: #2e3035; }
+.avatar { width: 40px; height: 40px; border-radius: 50%; flex-shrink: 0; }
+.body { min-width: 0; }
+.author { font-weight: 600; color: #f2f3f5; }
+.bot { margin-left: 4px; padding: 0 4px; border-radius: 3px; background: #5865f2; color: #fff; font-size: 11px; }
+.time { margin-left: 8px; color: #949ba4; font-size: 12px; }
+.content { white-space: pre-wrap; word-wrap: break-word; }
+.attachment { display: block; color: #00a8fc; }
+.embed { margin-top: 4px; padding: 8px 12px; max-width: 520px; border-left: 4px solid #1e1f22; border-radius: 4px; background: #2b2d31; }
+.embed-title { font-weight: 600; color: #f2f3f5; }
+.embed-field { margin-top: 4px; }
+.embed-field b { display: block; }
+.embed-footer { margin-top: 6px; color: #949ba4; font-size: 12px; }
+.embed img { display: block; max-width: 100%; margin-top: 6px; border-radius: 4px; }
+a { color: #00a8fc; }
+</style>
+";
+
+fn render_message(html: &mut String, message: &TranscriptMessage) {
+    let author = &message.author;
+
+    html.push_str(&format!(
+        "<div class=\"message\" id=\"m{}\">\n<img class=\"avatar\" src=\"{}\" alt=\"\">\n<div class=\"body\">\n",
+        message.id,
+        escape(&author.avatar_url)
+    ));
+    html.push_str(&format!(
+        "<span class=\"author\" title=\"{}\">{}</span>",
+        author.id,
+        escape(&author.tag)
+    ));
+
+    if author.bot {
+        html.push_str("<span class=\"bot\">BOT</span>");
+    }
+
+    html.push_str(&format!(
+        "<span class=\"time\">{}",
+        format_time(message.created_at)
+    ));
+
+    if let Some(edited_at) = message.edited_at {
+        html.push_str(&format!(" (editada em {})", format_time(edited_at)));
+    }
+
+    html.push_str("</span>\n");
+
+    if !message.content.is_empty() {
+        html.push_str(&format!(
+            "<div class=\"content\">{}</div>\n",
+            escape(&message.content)
+        ));
+    }
+
+    for attachment in message.attachments.iter() {
+        html.push_str(&format!(
+            "<a class=\"attachment\" href=\"{}\">{} ({} KB)</a>\n",
+            escape(&attachment.url),
+            escape(&attachment.filename),
+            attachment.size / 1024
+        ));
+    }
+
+    for embed in message.embeds.iter() {
+        render_embed(html, embed);
+    }
+
+    html.push_str("</div>\n</div>\n");
+}
+
+fn render_embed(html: &mut String, embed: &Embed) {
+    let colour = embed
+        .colour
+        .map(|colour| format!(" style=\"border-color: #{}\"", colour.hex()))
+        .unwrap_or_default();
+
+    html.push_str(&format!("<div class=\"embed\"{colour}>\n"));
+
+    if let Some(author) = embed.author.as_ref() {
+        html.push_str(&format!("<div>{}</div>\n", escape(&author.name)));
+    }
+
+    if let Some(title) = embed.title.as_ref() {
+        let title = match embed.url.as_ref() {
+            Some(url) => format!("<a href=\"{}\">{}</a>", escape(url), escape(title)),
+            None => escape(title),
+        };
+
+        html.push_str(&format!("<div class=\"embed-title\">{title}</div>\n"));
+    }
+
+    if let Some(description) = embed.description.as_ref() {
+        html.push_str(&format!(
+            "<div class=\"content\">{}</div>\n",
+            escape(description)
+        ));
+    }
+
+    for field in embed.fields.iter() {
+        html.push_str(&format!(
+            "<div class=\"embed-field\"><b>{}</b><span class=\"content\">{}</span></div>\n",
+            escape(&field.name),
+            escape(&field.value)
+        ));
+    }
+
+    if let Some(image) = embed.image.as_ref() {
+        html.push_str(&format!("<img src=\"{}\" alt=\"\">\n", escape(&image.url)));
+    }
+
+    if let Some(footer) = embed.footer.as_ref() {
+        html.push_str(&format!(
+            "<div class=\"embed-footer\">{}</div>\n",
+            escape(&footer.text)
+        ));
+    }
+
+    html.push_str("</div>\n");
+}
+
+fn format_time(timestamp: i64) -> String {
+    Utc.timestamp_opt(timestamp, 0)
+        .single()
+        .map(|time| time.format("%d/%m/%Y %H:%M UTC").to_string())
+        .unwrap_or_default()
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
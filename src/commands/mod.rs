@@ -1,2 +1,3 @@
 pub mod ping;
 pub mod play;
+pub mod playnext;
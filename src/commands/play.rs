@@ -18,15 +18,24 @@ use serenity::{
 
 use crate::music::{
     player::{self, PlayerError, PlayerStatus},
+    playlist::Placement,
     send_media_message,
 };
 
 pub async fn run(ctx: &Context, command: &ApplicationCommandInteraction) -> String {
+    enqueue(ctx, command, Placement::Back).await
+}
+
+pub async fn enqueue(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    placement: Placement,
+) -> String {
     let guild_id = command.guild_id.unwrap();
     let channel_id = command.channel_id;
     let options = command.data.options.clone();
 
-    let uri_option = options.get(0).unwrap().resolved.as_ref().unwrap();
+    let uri_option = options.first().unwrap().resolved.as_ref().unwrap();
 
     let uri: String = match uri_option {
         CommandDataOptionValue::String(uri) => uri.to_owned(),
@@ -35,27 +44,28 @@ pub async fn run(ctx: &Context, command: &ApplicationCommandInteraction) -> Stri
 
     let member = command.member.as_ref().unwrap().clone();
 
-    let status = player::add(ctx.clone(), uri, guild_id, channel_id, member).await;
+    let status = player::add(ctx.clone(), uri, guild_id, channel_id, member, placement).await;
 
-    let content: &str = match status {
-        Ok(PlayerStatus::Queued) => "A sua música foi adicionada na playlist.",
+    let content = match status {
+        Ok(PlayerStatus::Queued(queue_position)) => {
+            format!("A sua música foi adicionada na playlist, {queue_position}.")
+        }
+        Ok(PlayerStatus::Skipped) => "Música atual pulada, a sua já vai tocar.".to_string(),
         Ok(PlayerStatus::Playing(media_info)) => {
             send_media_message(
-                &ctx,
+                ctx,
                 command.member.as_ref().unwrap(),
                 channel_id,
                 media_info.to_owned(),
             )
             .await;
 
-            "Sua musga ta tocando porra!!!"
+            "Sua musga ta tocando porra!!!".to_string()
         }
-        Err(PlayerError::MusicNotFound) => "Musica não encontrada.",
-        Err(PlayerError::UserOffVoiceChannel) => "Você precisa estar em um canal de voz.",
-        _ => "Não foi possível obter um resultado.",
+        Err(why) => why.to_string(),
     };
 
-    content.to_string()
+    content
 }
 
 pub fn register(command: &mut CreateApplicationCommand) -> &mut CreateApplicationCommand {
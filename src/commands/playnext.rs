@@ -0,0 +1,43 @@
+use serenity::{
+    builder::CreateApplicationCommand,
+    model::prelude::{
+        command::{CommandOptionType, CommandType},
+        interaction::application_command::ApplicationCommandInteraction,
+    },
+    prelude::Context,
+};
+
+use super::play;
+use crate::music::playlist::Placement;
+
+// playnext, playtop e playskip só mudam onde a música entra na fila.
+pub async fn run(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+    placement: Placement,
+) -> String {
+    play::enqueue(ctx, command, placement).await
+}
+
+pub fn register(
+    command: &mut CreateApplicationCommand,
+    placement: Placement,
+) -> &mut CreateApplicationCommand {
+    let (name, description) = match placement {
+        Placement::Skip => ("playskip", "Pula a música atual e toca a sua."),
+        Placement::Top => ("playtop", "Coloca a música na frente de toda a fila."),
+        _ => ("playnext", "Toca a música logo depois da atual."),
+    };
+
+    command
+        .name(name)
+        .kind(CommandType::ChatInput)
+        .create_option(|option| {
+            option
+                .name("uri")
+                .description("Parametro de busca.")
+                .kind(CommandOptionType::String)
+                .required(true)
+        })
+        .description(description)
+}
@@ -0,0 +1,24 @@
+use lazy_static::lazy_static;
+use redis::{aio::Connection, Client, RedisResult};
+
+const REDIS_URL: &str = dotenv!("REDIS_URL");
+
+lazy_static! {
+    static ref CLIENT: Client = Client::open(REDIS_URL).expect("URL do Redis inválida.");
+}
+
+pub async fn connection() -> RedisResult<Connection> {
+    CLIENT.get_async_connection().await
+}
+
+pub fn guild_key(guild_id: u64, name: &str) -> String {
+    format!("bender:guild:{guild_id}:{name}")
+}
+
+pub fn key(name: &str) -> String {
+    format!("bender:{name}")
+}
+
+pub fn user_key(user_id: u64, name: &str) -> String {
+    format!("bender:user:{user_id}:{name}")
+}
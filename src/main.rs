@@ -5,6 +5,7 @@ extern crate dotenv_codegen;
 extern crate chrono;
 extern crate redis;
 
+use std::collections::HashSet;
 use std::ops::{Sub, SubAssign};
 use std::thread;
 use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
@@ -17,9 +18,11 @@ use serenity::framework::standard::{
     macros::{command, group},
     CommandResult, StandardFramework,
 };
+use serenity::http::Http;
+use serenity::model::event::MessageUpdateEvent;
 use serenity::model::prelude::interaction::application_command::ApplicationCommandInteraction;
 use serenity::model::prelude::interaction::{Interaction, InteractionResponseType};
-use serenity::model::prelude::{GuildId, Message, Ready};
+use serenity::model::prelude::{GuildId, Message, Ready, ResumedEvent};
 use serenity::prelude::{EventHandler, GatewayIntents};
 use serenity_additions::ephemeral_message::EphemeralMessage;
 use serenity_additions::RegisterAdditions;
@@ -30,6 +33,8 @@ use tracing_subscriber::fmt::format;
 use backup::*;
 use chat::*;
 use commands::*;
+use moderation::*;
+use music::playlist::Placement;
 use music::*;
 use network::*;
 use status::*;
@@ -37,11 +42,28 @@ use status::*;
 mod backup;
 mod chat;
 mod commands;
+mod database;
+mod moderation;
 mod music;
 mod network;
+mod permissions;
+mod scheduler;
+mod settings;
 mod status;
 
-const TOKEN: &'static str = dotenv!("DISCORD_TOKEN");
+const TOKEN: &str = dotenv!("DISCORD_TOKEN");
+
+#[hook]
+async fn before(ctx: &Context, msg: &Message, command_name: &str) -> bool {
+    match permissions::check_message(ctx, msg, command_name).await {
+        Ok(_) => true,
+        Err(why) => {
+            let _ = msg.reply(&ctx.http, why.to_string()).await;
+
+            false
+        }
+    }
+}
 
 #[hook]
 async fn unknown_command(ctx: &Context, msg: &Message, unknown_command_name: &str) {
@@ -58,6 +80,12 @@ struct Handler;
 #[async_trait]
 impl EventHandler for Handler {
     async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        if let Interaction::MessageComponent(component) = interaction {
+            music::menu::handle_component(&ctx, &component).await;
+
+            return;
+        }
+
         let command = interaction.application_command().unwrap();
         let typing = command.channel_id.start_typing(&ctx.http).unwrap();
 
@@ -66,10 +94,16 @@ impl EventHandler for Handler {
             .await
             .expect("Não foi possível adiar a resposta da interação.");
 
-        let content = match command.data.name.as_str() {
-            "ping" => commands::ping::run(&command.data.options).await,
-            "play" => commands::play::run(&ctx, &command).await,
-            _ => "Sem implementação para este comando.".to_string(),
+        let content = match permissions::check_interaction(&ctx, &command).await {
+            Err(why) => why.to_string(),
+            Ok(_) => match command.data.name.as_str() {
+                "ping" => commands::ping::run(&command.data.options).await,
+                "play" => commands::play::run(&ctx, &command).await,
+                "playnext" => commands::playnext::run(&ctx, &command, Placement::Front).await,
+                "playtop" => commands::playnext::run(&ctx, &command, Placement::Top).await,
+                "playskip" => commands::playnext::run(&ctx, &command, Placement::Skip).await,
+                _ => "Sem implementação para este comando.".to_string(),
+            },
         };
 
         command
@@ -80,6 +114,20 @@ impl EventHandler for Handler {
         typing.stop().unwrap();
     }
 
+    async fn message(&self, ctx: Context, msg: Message) {
+        moderation::scan(&ctx, &msg).await;
+    }
+
+    async fn message_update(
+        &self,
+        ctx: Context,
+        _: Option<Message>,
+        _: Option<Message>,
+        event: MessageUpdateEvent,
+    ) {
+        moderation::scan_edit(&ctx, &event).await;
+    }
+
     async fn ready(&self, ctx: Context, ready: Ready) {
         let guilds = ctx.http.get_guilds(None, None).await.unwrap();
 
@@ -92,23 +140,63 @@ impl EventHandler for Handler {
                     commands
                         .create_application_command(|command| commands::ping::register(command))
                         .create_application_command(|command| commands::play::register(command))
+                        .create_application_command(|command| {
+                            commands::playnext::register(command, Placement::Front)
+                        })
+                        .create_application_command(|command| {
+                            commands::playnext::register(command, Placement::Top)
+                        })
+                        .create_application_command(|command| {
+                            commands::playnext::register(command, Placement::Skip)
+                        })
                 })
                 .await
                 .unwrap();
+
+            music::player::rejoin(&ctx, guild_id).await;
+        }
+    }
+
+    async fn resume(&self, ctx: Context, _: ResumedEvent) {
+        for guild_id in ctx.cache.guilds() {
+            music::player::rejoin(&ctx, guild_id).await;
         }
     }
 }
 
 #[tokio::main]
 async fn main() {
+    tracing_subscriber::fmt::init();
+
+    let http = Http::new(TOKEN);
+
+    let owners = match http.get_current_application_info().await {
+        Ok(info) => {
+            let mut owners = HashSet::new();
+
+            match info.team {
+                Some(team) => owners.extend(team.members.iter().map(|member| member.user.id)),
+                None => {
+                    owners.insert(info.owner.id);
+                }
+            }
+
+            owners
+        }
+        Err(why) => panic!("Could not access application info: {:?}", why),
+    };
+
     let framework = StandardFramework::new()
-        .configure(|c| c.prefix("!"))
+        .configure(|c| c.owners(owners).prefix("!"))
+        .before(before)
         .unrecognised_command(unknown_command)
         .group(&CHAT_GROUP)
         .group(&MUSIC_GROUP)
+        .group(&MODERATION_GROUP)
         .group(&NETWORK_GROUP)
         .group(&BACKUP_GROUP)
-        .group(&STATUS_GROUP);
+        .group(&STATUS_GROUP)
+        .group(&permissions::ACCESS_GROUP);
 
     let intents = GatewayIntents::non_privileged()
         | GatewayIntents::GUILDS
@@ -124,6 +212,8 @@ async fn main() {
         .await
         .expect("Error creating client");
 
+    scheduler::start(client.cache_and_http.http.clone());
+
     if let Err(why) = client.start().await {
         println!("An error occurred while running the client: {:?}", why);
     }
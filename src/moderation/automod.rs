@@ -0,0 +1,431 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{GuildId, Message, Timestamp},
+    prelude::{Context, Mentionable},
+};
+use tokio::sync::Mutex;
+
+use super::infraction::{self, InfractionKind};
+use super::log::{record, LogEntry};
+use super::{is_moderator, notify};
+use crate::settings;
+
+const SPAM_WINDOW: Duration = Duration::from_secs(5);
+const DUPLICATE_WINDOW: Duration = Duration::from_secs(30);
+const CAPS_MIN_LETTERS: usize = 10;
+const AUTOMOD_TIMEOUT: i64 = 10 * 60;
+
+// Mensagens recentes por (servidor, usuário).
+type History = HashMap<(u64, u64), VecDeque<(Instant, String)>>;
+
+lazy_static! {
+    static ref INVITE: Regex =
+        Regex::new(r"(?i)(discord\.gg|discord(app)?\.com/invite)/[a-z0-9-]+").unwrap();
+    static ref HISTORY: Mutex<History> = Mutex::new(HashMap::new());
+    static ref LAST_SWEEP: Mutex<Instant> = Mutex::new(Instant::now());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleKind {
+    Spam,
+    Duplicates,
+    Mentions,
+    Invites,
+    Caps,
+}
+
+impl RuleKind {
+    pub const ALL: [RuleKind; 5] = [
+        RuleKind::Spam,
+        RuleKind::Duplicates,
+        RuleKind::Mentions,
+        RuleKind::Invites,
+        RuleKind::Caps,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        RuleKind::ALL
+            .into_iter()
+            .find(|kind| kind.command_name() == value)
+    }
+
+    pub fn command_name(&self) -> &'static str {
+        match self {
+            RuleKind::Spam => "spam",
+            RuleKind::Duplicates => "duplicates",
+            RuleKind::Mentions => "mentions",
+            RuleKind::Invites => "invites",
+            RuleKind::Caps => "caps",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RuleKind::Spam => "Excesso de mensagens",
+            RuleKind::Duplicates => "Mensagens repetidas",
+            RuleKind::Mentions => "Excesso de menções",
+            RuleKind::Invites => "Convites do Discord",
+            RuleKind::Caps => "Excesso de maiúsculas",
+        }
+    }
+
+    pub fn limit_description(&self, limit: u32) -> String {
+        match self {
+            RuleKind::Spam => format!("{limit} mensagens em {}s", SPAM_WINDOW.as_secs()),
+            RuleKind::Duplicates => {
+                format!(
+                    "{limit} mensagens iguais em {}s",
+                    DUPLICATE_WINDOW.as_secs()
+                )
+            }
+            RuleKind::Mentions => format!("{limit} menções por mensagem"),
+            RuleKind::Invites => "qualquer convite".to_string(),
+            RuleKind::Caps => format!("{limit}% de maiúsculas"),
+        }
+    }
+
+    fn default_limit(&self) -> u32 {
+        match self {
+            RuleKind::Spam => 5,
+            RuleKind::Duplicates => 3,
+            RuleKind::Mentions => 5,
+            RuleKind::Invites => 1,
+            RuleKind::Caps => 70,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AutomodAction {
+    #[default]
+    Delete,
+    Warn,
+    Timeout,
+}
+
+impl AutomodAction {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "delete" => Some(AutomodAction::Delete),
+            "warn" => Some(AutomodAction::Warn),
+            "timeout" => Some(AutomodAction::Timeout),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AutomodAction::Delete => "apagar",
+            AutomodAction::Warn => "advertir",
+            AutomodAction::Timeout => "castigar",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomodRule {
+    pub enabled: bool,
+    pub action: AutomodAction,
+    pub limit: Option<u32>,
+    pub exempt_roles: Vec<u64>,
+    pub exempt_channels: Vec<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomodSettings {
+    pub spam: AutomodRule,
+    pub duplicates: AutomodRule,
+    pub mentions: AutomodRule,
+    pub invites: AutomodRule,
+    pub caps: AutomodRule,
+}
+
+impl AutomodSettings {
+    pub fn rule(&self, kind: RuleKind) -> &AutomodRule {
+        match kind {
+            RuleKind::Spam => &self.spam,
+            RuleKind::Duplicates => &self.duplicates,
+            RuleKind::Mentions => &self.mentions,
+            RuleKind::Invites => &self.invites,
+            RuleKind::Caps => &self.caps,
+        }
+    }
+
+    pub fn rule_mut(&mut self, kind: RuleKind) -> &mut AutomodRule {
+        match kind {
+            RuleKind::Spam => &mut self.spam,
+            RuleKind::Duplicates => &mut self.duplicates,
+            RuleKind::Mentions => &mut self.mentions,
+            RuleKind::Invites => &mut self.invites,
+            RuleKind::Caps => &mut self.caps,
+        }
+    }
+
+    pub fn limit(&self, kind: RuleKind) -> u32 {
+        self.rule(kind).limit.unwrap_or(kind.default_limit())
+    }
+}
+
+pub async fn check(ctx: &Context, msg: &Message) {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) if !msg.author.bot => guild_id,
+        _ => return,
+    };
+
+    let settings = settings::get(guild_id.0).await.automod;
+
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+
+    if is_moderator(ctx, guild_id, msg.author.id, &roles) {
+        return;
+    }
+
+    let recent = remember(guild_id, msg).await;
+
+    for kind in RuleKind::ALL {
+        let rule = settings.rule(kind);
+
+        if !rule.enabled || rule.exempt_channels.contains(&msg.channel_id.0) {
+            continue;
+        }
+
+        if roles.iter().any(|role| rule.exempt_roles.contains(&role.0)) {
+            continue;
+        }
+
+        let limit = settings.limit(kind) as usize;
+
+        let violated = match kind {
+            RuleKind::Spam => {
+                recent
+                    .iter()
+                    .filter(|(at, _)| at.elapsed() <= SPAM_WINDOW)
+                    .count()
+                    >= limit
+            }
+            RuleKind::Duplicates => {
+                !msg.content.is_empty()
+                    && recent
+                        .iter()
+                        .filter(|(_, content)| *content == msg.content)
+                        .count()
+                        >= limit
+            }
+            RuleKind::Mentions => {
+                let everyone = if msg.mention_everyone { 1 } else { 0 };
+
+                msg.mentions.len() + msg.mention_roles.len() + everyone >= limit
+            }
+            RuleKind::Invites => INVITE.is_match(&msg.content),
+            RuleKind::Caps => is_shouting(&msg.content, limit),
+        };
+
+        if violated {
+            // Sem isso cada mensagem seguinte puniria de novo pela mesma rajada.
+            HISTORY.lock().await.remove(&(guild_id.0, msg.author.id.0));
+
+            punish(ctx, guild_id, msg, kind, rule.action).await;
+
+            return;
+        }
+    }
+}
+
+async fn remember(guild_id: GuildId, msg: &Message) -> Vec<(Instant, String)> {
+    let mut history = HISTORY.lock().await;
+
+    let mut last_sweep = LAST_SWEEP.lock().await;
+
+    if last_sweep.elapsed() > DUPLICATE_WINDOW {
+        sweep(&mut history, Instant::now());
+
+        *last_sweep = Instant::now();
+    }
+
+    drop(last_sweep);
+
+    let recent = history
+        .entry((guild_id.0, msg.author.id.0))
+        .or_insert_with(VecDeque::new);
+
+    while recent
+        .front()
+        .is_some_and(|(at, _)| at.elapsed() > DUPLICATE_WINDOW)
+    {
+        recent.pop_front();
+    }
+
+    recent.push_back((Instant::now(), msg.content.clone()));
+
+    recent.iter().cloned().collect()
+}
+
+// De tempos em tempos remove quem não fala há mais que a maior janela.
+fn sweep(history: &mut History, now: Instant) {
+    history.retain(|_, recent| {
+        recent
+            .back()
+            .is_some_and(|(at, _)| now.duration_since(*at) <= DUPLICATE_WINDOW)
+    });
+}
+
+fn is_shouting(content: &str, limit: usize) -> bool {
+    let letters = content
+        .chars()
+        .filter(|c| c.is_alphabetic())
+        .collect::<Vec<char>>();
+
+    if letters.len() < CAPS_MIN_LETTERS {
+        return false;
+    }
+
+    let upper = letters.iter().filter(|c| c.is_uppercase()).count();
+
+    upper * 100 >= letters.len() * limit
+}
+
+async fn punish(
+    ctx: &Context,
+    guild_id: GuildId,
+    msg: &Message,
+    kind: RuleKind,
+    action: AutomodAction,
+) {
+    let _ = msg.delete(&ctx.http).await;
+
+    let bot_id = ctx.cache.current_user_id();
+    let reason = format!("Automod: {}", kind.name());
+
+    let (infraction_kind, duration, applied) = match action {
+        AutomodAction::Delete => (None, None, action),
+        AutomodAction::Warn => (Some(InfractionKind::Warn), None, action),
+        AutomodAction::Timeout => {
+            let until =
+                Timestamp::from_unix_timestamp(Utc::now().timestamp() + AUTOMOD_TIMEOUT).unwrap();
+
+            let result = guild_id
+                .edit_member(&ctx.http, msg.author.id, |m| {
+                    m.disable_communication_until_datetime(until)
+                })
+                .await;
+
+            // Sem permissão para castigar a mensagem só foi apagada.
+            match result {
+                Ok(_) => (
+                    Some(InfractionKind::Timeout),
+                    Some(AUTOMOD_TIMEOUT as u64),
+                    action,
+                ),
+                Err(_) => (None, None, AutomodAction::Delete),
+            }
+        }
+    };
+
+    let _ = msg
+        .channel_id
+        .say(
+            &ctx.http,
+            format!(
+                "{}, sua mensagem foi removida: {}.",
+                msg.author.mention(),
+                kind.name().to_lowercase()
+            ),
+        )
+        .await;
+
+    let mut entry = LogEntry::new(format!("Automod | {}", kind.name()), bot_id)
+        .target(format!("{} ({})", msg.author.mention(), msg.author.tag()))
+        .reason(Some(&reason))
+        .detail("Canal", msg.channel_id.mention())
+        .detail("Ação", applied.name());
+
+    if applied != action {
+        entry = entry.detail(
+            "Falha",
+            format!("Não foi possível {} o membro.", action.name()),
+        );
+    }
+
+    if let Some(infraction_kind) = infraction_kind {
+        let reason = Some(reason.clone());
+
+        notify(ctx, guild_id, &msg.author, infraction_kind, &reason).await;
+
+        let created = infraction::create(
+            guild_id.0,
+            infraction_kind,
+            msg.author.id.0,
+            bot_id.0,
+            reason,
+            duration,
+        )
+        .await;
+
+        if let Ok(infraction) = created {
+            entry = entry.detail("Caso", format!("#{}", infraction.case));
+        }
+    }
+
+    let content = msg.content.chars().take(1000).collect::<String>();
+
+    if !content.is_empty() {
+        entry = entry.detail("Mensagem", content);
+    }
+
+    record(ctx, guild_id, msg.channel_id, entry).await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn is_shouting_ignores_short_messages() {
+        assert!(!is_shouting("AAAAAAAAA", 70));
+        assert!(!is_shouting("OI! 123 :) ?", 70));
+    }
+
+    #[test]
+    fn is_shouting_counts_only_letters() {
+        assert!(is_shouting("PARA DE FAZER ISSO!!! 1234", 70));
+        assert!(is_shouting("ESTOU GRITANDO aqui", 70));
+        assert!(!is_shouting("ESTOU quase gritando", 70));
+        assert!(is_shouting("ÇÃO É ASSIM MESMO", 100));
+    }
+
+    #[test]
+    fn sweep_removes_only_idle_users() {
+        let start = Instant::now();
+        let mut history = History::new();
+
+        history.insert((1, 1), VecDeque::from([(start, "a".to_string())]));
+        history.insert(
+            (1, 2),
+            VecDeque::from([
+                (start, "b".to_string()),
+                (start + DUPLICATE_WINDOW, "c".to_string()),
+            ]),
+        );
+        history.insert((1, 3), VecDeque::new());
+
+        let now = start + DUPLICATE_WINDOW + Duration::from_secs(1);
+
+        sweep(&mut history, now);
+
+        assert_eq!(history.keys().collect::<Vec<_>>(), vec![&(1, 2)]);
+    }
+}
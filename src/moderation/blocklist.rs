@@ -0,0 +1,535 @@
+use std::{collections::HashMap, sync::Arc};
+
+use lazy_static::lazy_static;
+use redis::AsyncCommands;
+use regex::{Regex, RegexBuilder, RegexSet, RegexSetBuilder};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{ChannelId, GuildId, MessageId, RoleId, User},
+    prelude::{Context, Mentionable},
+};
+use tokio::sync::Mutex;
+
+use super::is_moderator;
+use super::log::{record, LogEntry};
+use crate::database;
+
+type BlocklistResult<T> = Result<T, BlocklistError>;
+
+pub const MAX_ENTRIES: usize = 200;
+const MAX_PATTERN_LENGTH: usize = 200;
+const REGEX_SIZE_LIMIT: usize = 1 << 20;
+const SET_SIZE_LIMIT: usize = REGEX_SIZE_LIMIT * 10;
+const ZERO_WIDTH: [char; 7] = [
+    '\u{200b}', '\u{200c}', '\u{200d}', '\u{200e}', '\u{200f}', '\u{2060}', '\u{feff}',
+];
+
+lazy_static! {
+    static ref MATCHERS: Mutex<HashMap<u64, Arc<Matcher>>> = Mutex::new(HashMap::new());
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EntryKind {
+    Word,
+    Regex,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedEntry {
+    pub kind: EntryKind,
+    pub pattern: String,
+}
+
+impl BlockedEntry {
+    pub fn name(&self) -> String {
+        match self.kind {
+            EntryKind::Word => format!("`{}`", self.pattern),
+            EntryKind::Regex => format!("regex `{}`", self.pattern),
+        }
+    }
+
+    fn field(&self) -> String {
+        match self.kind {
+            EntryKind::Word => format!("word:{}", self.pattern),
+            EntryKind::Regex => format!("regex:{}", self.pattern),
+        }
+    }
+
+    fn regex(&self) -> String {
+        match self.kind {
+            EntryKind::Word => word_regex(&normalize(&self.pattern)),
+            EntryKind::Regex => self.pattern.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum BlocklistError {
+    InvalidPattern,
+    InvalidRegex,
+    LimitReached,
+    AlreadyExists,
+    NotFound,
+    TooLarge,
+    Database,
+}
+
+impl From<redis::RedisError> for BlocklistError {
+    fn from(_: redis::RedisError) -> Self {
+        BlocklistError::Database
+    }
+}
+
+impl std::fmt::Display for BlocklistError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlocklistError::InvalidPattern => write!(
+                f,
+                "O termo deve ter entre 1 e {MAX_PATTERN_LENGTH} caracteres."
+            ),
+            BlocklistError::InvalidRegex => write!(f, "Expressão regular inválida."),
+            BlocklistError::LimitReached => {
+                write!(f, "A lista pode ter no máximo {MAX_ENTRIES} termos.")
+            }
+            BlocklistError::AlreadyExists => write!(f, "Esse termo já está na lista."),
+            BlocklistError::NotFound => write!(f, "Esse termo não está na lista."),
+            BlocklistError::TooLarge => write!(
+                f,
+                "A lista ficou complexa demais, remova alguns termos antes de adicionar outros."
+            ),
+            BlocklistError::Database => write!(f, "Não foi possível acessar a lista."),
+        }
+    }
+}
+
+// Palavras são comparadas com o texto normalizado e regexes com o texto só
+// sem caracteres invisíveis, mas tudo fica num único RegexSet.
+struct Matcher {
+    set: RegexSet,
+    entries: Vec<BlockedEntry>,
+    words: usize,
+}
+
+impl Matcher {
+    // Um termo inválido salvo antes não pode derrubar a lista inteira. Se o
+    // conjunto passar do limite, os últimos termos ficam de fora até caber.
+    fn build(entries: Vec<BlockedEntry>) -> Self {
+        let mut entries = valid_entries(entries);
+
+        entries.sort_by_key(|entry| entry.kind == EntryKind::Regex);
+
+        let set = match compile(&entries) {
+            Ok(set) => set,
+            Err(why) => {
+                let (set, fits) = largest_prefix(&entries);
+
+                for skipped in entries.drain(fits..) {
+                    tracing::warn!("Ignorando o termo bloqueado {}: {why}", skipped.name());
+                }
+
+                set
+            }
+        };
+
+        let words = entries
+            .iter()
+            .filter(|entry| entry.kind == EntryKind::Word)
+            .count();
+
+        Matcher {
+            set,
+            entries,
+            words,
+        }
+    }
+
+    fn find(&self, content: &str) -> Vec<&BlockedEntry> {
+        let cleaned = strip_invisible(content);
+        let normalized = normalize(content);
+
+        let words = self
+            .set
+            .matches(&normalized)
+            .into_iter()
+            .filter(|i| *i < self.words);
+        let regexes = self
+            .set
+            .matches(&cleaned)
+            .into_iter()
+            .filter(|i| *i >= self.words);
+
+        words.chain(regexes).map(|i| &self.entries[i]).collect()
+    }
+}
+
+pub async fn add(guild_id: u64, kind: EntryKind, pattern: &str) -> BlocklistResult<BlockedEntry> {
+    let entry = parse_entry(kind, pattern)?;
+
+    let mut connection = database::connection().await?;
+    let key = database::guild_key(guild_id, "blocklist");
+
+    let exists: bool = connection.hexists(&key, entry.field()).await?;
+
+    if exists {
+        return Err(BlocklistError::AlreadyExists);
+    }
+
+    let count: usize = connection.hlen(&key).await?;
+
+    if count >= MAX_ENTRIES {
+        return Err(BlocklistError::LimitReached);
+    }
+
+    // O RegexSet tem um limite de tamanho para a lista toda, não só por termo.
+    let mut entries = valid_entries(list(guild_id).await?);
+
+    entries.push(entry.clone());
+
+    if compile(&entries).is_err() {
+        return Err(BlocklistError::TooLarge);
+    }
+
+    let value = serde_json::to_string(&entry).unwrap();
+
+    let _: () = connection.hset(&key, entry.field(), value).await?;
+
+    MATCHERS.lock().await.remove(&guild_id);
+
+    Ok(entry)
+}
+
+pub async fn remove(guild_id: u64, kind: EntryKind, pattern: &str) -> BlocklistResult<()> {
+    let entry = BlockedEntry {
+        kind,
+        pattern: pattern.trim().to_string(),
+    };
+
+    let mut connection = database::connection().await?;
+
+    let removed: usize = connection
+        .hdel(database::guild_key(guild_id, "blocklist"), entry.field())
+        .await?;
+
+    if removed == 0 {
+        return Err(BlocklistError::NotFound);
+    }
+
+    MATCHERS.lock().await.remove(&guild_id);
+
+    Ok(())
+}
+
+pub async fn list(guild_id: u64) -> BlocklistResult<Vec<BlockedEntry>> {
+    let mut connection = database::connection().await?;
+
+    let values: HashMap<String, String> = connection
+        .hgetall(database::guild_key(guild_id, "blocklist"))
+        .await?;
+
+    let mut entries = values
+        .values()
+        .filter_map(|value| serde_json::from_str::<BlockedEntry>(value).ok())
+        .collect::<Vec<BlockedEntry>>();
+
+    entries.sort_by_key(BlockedEntry::field);
+
+    Ok(entries)
+}
+
+pub async fn test(guild_id: u64, content: &str) -> BlocklistResult<Vec<BlockedEntry>> {
+    let matcher = matcher(guild_id).await?;
+
+    Ok(matcher.find(content).into_iter().cloned().collect())
+}
+
+pub async fn check(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    message_id: MessageId,
+    author: &User,
+    roles: &[RoleId],
+    content: &str,
+) -> bool {
+    if author.bot || content.is_empty() || is_moderator(ctx, guild_id, author.id, roles) {
+        return false;
+    }
+
+    let matcher = match matcher(guild_id.0).await {
+        Ok(matcher) => matcher,
+        Err(_) => return false,
+    };
+
+    let found = matcher.find(content);
+
+    if found.is_empty() {
+        return false;
+    }
+
+    if channel_id
+        .delete_message(&ctx.http, message_id)
+        .await
+        .is_err()
+    {
+        return false;
+    }
+
+    let guild_name = ctx
+        .cache
+        .guild_field(guild_id, |g| g.name.clone())
+        .unwrap_or_default();
+
+    let _ = author
+        .direct_message(ctx, |m| {
+            m.content(format!(
+                "Sua mensagem em **{guild_name}** foi removida por conter um termo bloqueado."
+            ))
+        })
+        .await;
+
+    let terms = found
+        .iter()
+        .map(|entry| entry.name())
+        .collect::<Vec<String>>()
+        .join(", ");
+
+    record(
+        ctx,
+        guild_id,
+        channel_id,
+        LogEntry::new("Termo bloqueado", ctx.cache.current_user_id())
+            .target(format!("{} ({})", author.mention(), author.tag()))
+            .detail("Canal", channel_id.mention())
+            .detail("Termos", terms)
+            .detail("Mensagem", content.chars().take(1000).collect::<String>()),
+    )
+    .await;
+
+    true
+}
+
+async fn matcher(guild_id: u64) -> BlocklistResult<Arc<Matcher>> {
+    if let Some(matcher) = MATCHERS.lock().await.get(&guild_id) {
+        return Ok(matcher.clone());
+    }
+
+    let matcher = Arc::new(Matcher::build(list(guild_id).await?));
+
+    MATCHERS.lock().await.insert(guild_id, matcher.clone());
+
+    Ok(matcher)
+}
+
+fn valid_entries(entries: Vec<BlockedEntry>) -> Vec<BlockedEntry> {
+    entries
+        .into_iter()
+        .filter(|entry| match build_regex(&entry.regex()) {
+            Ok(_) => true,
+            Err(why) => {
+                tracing::warn!("Ignorando o termo bloqueado {}: {why}", entry.name());
+
+                false
+            }
+        })
+        .collect()
+}
+
+// Busca binária pelo maior começo da lista que ainda cabe no limite do
+// RegexSet, sem compilar a lista uma vez para cada termo.
+fn largest_prefix(entries: &[BlockedEntry]) -> (RegexSet, usize) {
+    let mut best = (RegexSet::empty(), 0);
+    let mut high = entries.len();
+
+    while high - best.1 > 1 {
+        let middle = (best.1 + high) / 2;
+
+        match compile(&entries[..middle]) {
+            Ok(set) => best = (set, middle),
+            Err(_) => high = middle,
+        }
+    }
+
+    best
+}
+
+// Mesmas opções do RegexSet, para um termo aceito no `add` nunca ser
+// descartado depois.
+fn build_regex(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(pattern)
+        .case_insensitive(true)
+        .size_limit(REGEX_SIZE_LIMIT)
+        .build()
+}
+
+fn compile(entries: &[BlockedEntry]) -> Result<RegexSet, regex::Error> {
+    RegexSetBuilder::new(entries.iter().map(BlockedEntry::regex))
+        .case_insensitive(true)
+        .size_limit(SET_SIZE_LIMIT)
+        .build()
+}
+
+// `\b` só faz sentido ao lado de letras e números, senão termos como `c++` ou
+// `@everyone` nunca casariam.
+fn word_regex(word: &str) -> String {
+    let is_word = |c: char| c.is_alphanumeric() || c == '_';
+
+    let start = if word.starts_with(is_word) { r"\b" } else { "" };
+    let end = if word.ends_with(is_word) { r"\b" } else { "" };
+
+    format!("{start}{}{end}", regex::escape(word))
+}
+
+fn parse_entry(kind: EntryKind, pattern: &str) -> BlocklistResult<BlockedEntry> {
+    let pattern = pattern.trim();
+
+    if pattern.is_empty() || pattern.chars().count() > MAX_PATTERN_LENGTH {
+        return Err(BlocklistError::InvalidPattern);
+    }
+
+    if kind == EntryKind::Word && normalize(pattern).trim().is_empty() {
+        return Err(BlocklistError::InvalidPattern);
+    }
+
+    let entry = BlockedEntry {
+        kind,
+        pattern: pattern.to_string(),
+    };
+
+    build_regex(&entry.regex()).map_err(|_| match kind {
+        EntryKind::Word => BlocklistError::InvalidPattern,
+        EntryKind::Regex => BlocklistError::InvalidRegex,
+    })?;
+
+    Ok(entry)
+}
+
+fn strip_invisible(content: &str) -> String {
+    content
+        .chars()
+        .filter(|c| !ZERO_WIDTH.contains(c) && *c != '\u{ad}')
+        .collect()
+}
+
+fn normalize(content: &str) -> String {
+    strip_invisible(content)
+        .to_lowercase()
+        .chars()
+        .map(|c| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' | '@' => 'a',
+            '5' | '$' => 's',
+            '7' => 't',
+            '8' => 'b',
+            '9' => 'g',
+            c => c,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: EntryKind, pattern: &str) -> BlockedEntry {
+        BlockedEntry {
+            kind,
+            pattern: pattern.to_string(),
+        }
+    }
+
+    #[test]
+    fn normalize_undoes_common_substitutions() {
+        assert_eq!(normalize("P4L4VR4 1NV3NT4D4"), "palavra inventada");
+        assert_eq!(normalize("$3GR3D0"), "segredo");
+        assert_eq!(normalize("ATENÇÃO"), "atenção");
+    }
+
+    #[test]
+    fn normalize_drops_invisible_characters() {
+        assert_eq!(normalize("pa\u{200b}la\u{ad}vra\u{feff}"), "palavra");
+    }
+
+    #[test]
+    fn word_regex_only_adds_boundaries_next_to_word_characters() {
+        assert_eq!(word_regex("abc"), r"\babc\b");
+        assert_eq!(word_regex("c++"), r"\bc\+\+");
+        assert_eq!(word_regex("@everyone"), r"@everyone\b");
+        assert_eq!(word_regex("..."), r"\.\.\.");
+    }
+
+    #[test]
+    fn word_regex_matches_whole_words_only() {
+        let regex = Regex::new(&word_regex("gato")).unwrap();
+
+        assert!(regex.is_match("um gato preto"));
+        assert!(!regex.is_match("gatos"));
+        assert!(Regex::new(&word_regex("c++"))
+            .unwrap()
+            .is_match("uso c++ hoje"));
+    }
+
+    #[test]
+    fn matcher_checks_words_normalized_and_regexes_as_written() {
+        let matcher = Matcher::build(vec![
+            entry(EntryKind::Word, "gato"),
+            entry(EntryKind::Regex, "[0-9]{4}"),
+        ]);
+
+        let found = |content| {
+            matcher
+                .find(content)
+                .iter()
+                .map(|entry| entry.pattern.clone())
+                .collect::<Vec<String>>()
+        };
+
+        assert_eq!(found("G4TO"), vec!["gato"]);
+        assert_eq!(found("senha 1234"), vec!["[0-9]{4}"]);
+        assert!(found("cachorro").is_empty());
+    }
+
+    #[test]
+    fn matcher_skips_only_invalid_entries() {
+        let matcher = Matcher::build(vec![
+            entry(EntryKind::Regex, "(aberto"),
+            entry(EntryKind::Word, "gato"),
+        ]);
+
+        assert_eq!(matcher.entries.len(), 1);
+        assert_eq!(matcher.find("gato").len(), 1);
+    }
+
+    #[test]
+    fn matcher_drops_the_last_regexes_when_the_set_is_too_large() {
+        let mut entries = (0..40)
+            .map(|i| entry(EntryKind::Regex, &format!(r"\w{{8}}{i}")))
+            .collect::<Vec<BlockedEntry>>();
+
+        entries.push(entry(EntryKind::Word, "gato"));
+
+        assert!(compile(&entries).is_err());
+
+        let matcher = Matcher::build(entries);
+
+        assert!(matcher.entries.len() > 1 && matcher.entries.len() < 41);
+        assert_eq!(matcher.find("gato").len(), 1);
+    }
+
+    #[test]
+    fn parse_entry_validates_like_the_matcher() {
+        assert!(matches!(
+            parse_entry(EntryKind::Regex, "(aberto"),
+            Err(BlocklistError::InvalidRegex)
+        ));
+        assert!(matches!(
+            parse_entry(EntryKind::Word, " $$ "),
+            Ok(entry) if entry.pattern == "$$"
+        ));
+        assert!(matches!(
+            parse_entry(EntryKind::Word, "   "),
+            Err(BlocklistError::InvalidPattern)
+        ));
+    }
+}
@@ -0,0 +1,212 @@
+use chrono::Utc;
+use redis::{aio::Connection, AsyncCommands};
+use serde::{Deserialize, Serialize};
+
+use crate::database;
+
+type InfractionResult<T> = Result<T, InfractionError>;
+
+pub const MAX_REASON_LENGTH: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum InfractionKind {
+    Warn,
+    Timeout,
+    Mute,
+    Unmute,
+    Kick,
+    Ban,
+    Unban,
+}
+
+impl InfractionKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            InfractionKind::Warn => "Advertência",
+            InfractionKind::Timeout => "Castigo",
+            InfractionKind::Mute => "Silenciamento",
+            InfractionKind::Unmute => "Fim do silenciamento",
+            InfractionKind::Kick => "Expulsão",
+            InfractionKind::Ban => "Banimento",
+            InfractionKind::Unban => "Desbanimento",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Infraction {
+    pub case: u64,
+    pub kind: InfractionKind,
+    pub user_id: u64,
+    pub moderator_id: u64,
+    pub reason: Option<String>,
+    pub duration: Option<u64>,
+    pub created_at: i64,
+}
+
+#[derive(Debug)]
+pub enum InfractionError {
+    ReasonTooLong,
+    NotFound,
+    Database,
+}
+
+impl From<redis::RedisError> for InfractionError {
+    fn from(_: redis::RedisError) -> Self {
+        InfractionError::Database
+    }
+}
+
+impl std::fmt::Display for InfractionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InfractionError::ReasonTooLong => write!(
+                f,
+                "O motivo deve ter no máximo {MAX_REASON_LENGTH} caracteres."
+            ),
+            InfractionError::NotFound => write!(f, "Caso não encontrado."),
+            InfractionError::Database => write!(f, "Não foi possível acessar o histórico."),
+        }
+    }
+}
+
+pub fn check_reason(reason: Option<&str>) -> InfractionResult<()> {
+    match reason {
+        Some(reason) if reason.chars().count() > MAX_REASON_LENGTH => {
+            Err(InfractionError::ReasonTooLong)
+        }
+        _ => Ok(()),
+    }
+}
+
+pub async fn create(
+    guild_id: u64,
+    kind: InfractionKind,
+    user_id: u64,
+    moderator_id: u64,
+    reason: Option<String>,
+    duration: Option<u64>,
+) -> InfractionResult<Infraction> {
+    check_reason(reason.as_deref())?;
+
+    let mut connection = database::connection().await?;
+
+    let case: u64 = connection
+        .incr(database::guild_key(guild_id, "cases"), 1)
+        .await?;
+
+    let infraction = Infraction {
+        case,
+        kind,
+        user_id,
+        moderator_id,
+        reason,
+        duration,
+        created_at: Utc::now().timestamp(),
+    };
+
+    write(&mut connection, guild_id, &infraction).await?;
+
+    let _: () = connection
+        .rpush(user_cases_key(guild_id, user_id), case)
+        .await?;
+
+    Ok(infraction)
+}
+
+pub async fn get(guild_id: u64, case: u64) -> InfractionResult<Infraction> {
+    let mut connection = database::connection().await?;
+
+    read(&mut connection, guild_id, case)
+        .await?
+        .ok_or(InfractionError::NotFound)
+}
+
+pub async fn list(guild_id: u64, user_id: u64) -> InfractionResult<Vec<Infraction>> {
+    let mut connection = database::connection().await?;
+
+    let cases: Vec<u64> = connection
+        .lrange(user_cases_key(guild_id, user_id), 0, -1)
+        .await?;
+
+    let mut infractions = vec![];
+
+    for case in cases {
+        if let Some(infraction) = read(&mut connection, guild_id, case).await? {
+            infractions.push(infraction);
+        }
+    }
+
+    Ok(infractions)
+}
+
+pub async fn edit_reason(guild_id: u64, case: u64, reason: String) -> InfractionResult<Infraction> {
+    check_reason(Some(&reason))?;
+
+    let mut connection = database::connection().await?;
+
+    let mut infraction = read(&mut connection, guild_id, case)
+        .await?
+        .ok_or(InfractionError::NotFound)?;
+
+    infraction.reason = Some(reason);
+
+    write(&mut connection, guild_id, &infraction).await?;
+
+    Ok(infraction)
+}
+
+fn user_cases_key(guild_id: u64, user_id: u64) -> String {
+    database::guild_key(guild_id, &format!("infractions:{user_id}"))
+}
+
+async fn read(
+    connection: &mut Connection,
+    guild_id: u64,
+    case: u64,
+) -> InfractionResult<Option<Infraction>> {
+    let value: Option<String> = connection
+        .hget(database::guild_key(guild_id, "infractions"), case)
+        .await?;
+
+    Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+}
+
+async fn write(
+    connection: &mut Connection,
+    guild_id: u64,
+    infraction: &Infraction,
+) -> InfractionResult<()> {
+    let value = serde_json::to_string(infraction).unwrap();
+
+    let _: () = connection
+        .hset(
+            database::guild_key(guild_id, "infractions"),
+            infraction.case,
+            value,
+        )
+        .await?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_reason_accepts_missing_and_short_reasons() {
+        assert!(check_reason(None).is_ok());
+        assert!(check_reason(Some("")).is_ok());
+        assert!(check_reason(Some(&"a".repeat(MAX_REASON_LENGTH))).is_ok());
+    }
+
+    #[test]
+    fn check_reason_counts_characters_not_bytes() {
+        assert!(check_reason(Some(&"ç".repeat(MAX_REASON_LENGTH))).is_ok());
+        assert!(matches!(
+            check_reason(Some(&"a".repeat(MAX_REASON_LENGTH + 1))),
+            Err(InfractionError::ReasonTooLong)
+        ));
+    }
+}
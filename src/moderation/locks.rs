@@ -0,0 +1,217 @@
+use redis::AsyncCommands;
+use serde::{Deserialize, Serialize};
+use serenity::{
+    model::prelude::{
+        ChannelType, GuildChannel, GuildId, PermissionOverwrite, PermissionOverwriteType,
+        Permissions, RoleId,
+    },
+    prelude::Context,
+};
+
+use crate::database;
+
+type LockResult<T> = Result<T, LockError>;
+
+const LOCKED: Permissions = Permissions::from_bits_truncate(
+    Permissions::SEND_MESSAGES.bits()
+        | Permissions::SEND_MESSAGES_IN_THREADS.bits()
+        | Permissions::CREATE_PUBLIC_THREADS.bits()
+        | Permissions::CREATE_PRIVATE_THREADS.bits(),
+);
+
+// Permissões do @everyone no canal antes do bloqueio, `None` quando não havia
+// nenhuma sobrescrita.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SavedOverwrite {
+    previous: Option<(u64, u64)>,
+}
+
+#[derive(Debug)]
+pub enum LockError {
+    AlreadyLocked,
+    NotLocked,
+    Failed,
+    Database,
+}
+
+impl From<redis::RedisError> for LockError {
+    fn from(_: redis::RedisError) -> Self {
+        LockError::Database
+    }
+}
+
+impl std::fmt::Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::AlreadyLocked => write!(f, "Esse canal já está bloqueado."),
+            LockError::NotLocked => write!(f, "Esse canal não foi bloqueado por mim."),
+            LockError::Failed => write!(
+                f,
+                "Não foi possível alterar as permissões, verifique as minhas permissões."
+            ),
+            LockError::Database => write!(f, "Não foi possível acessar os bloqueios salvos."),
+        }
+    }
+}
+
+pub fn is_lockable(channel: &GuildChannel) -> bool {
+    matches!(channel.kind, ChannelType::Text | ChannelType::News)
+}
+
+pub async fn lock(ctx: &Context, channel: &GuildChannel) -> LockResult<()> {
+    let everyone = everyone(channel.guild_id);
+    let mut connection = database::connection().await?;
+    let key = database::guild_key(channel.guild_id.0, "locks");
+
+    let locked: bool = connection.hexists(&key, channel.id.0).await?;
+
+    if locked {
+        return Err(LockError::AlreadyLocked);
+    }
+
+    let current = channel
+        .permission_overwrites
+        .iter()
+        .find(|overwrite| overwrite.kind == everyone);
+
+    let saved = SavedOverwrite {
+        previous: current.map(|overwrite| (overwrite.allow.bits(), overwrite.deny.bits())),
+    };
+
+    let overwrite = locked_overwrite(current, everyone);
+
+    let value = serde_json::to_string(&saved).unwrap();
+
+    let _: () = connection.hset(&key, channel.id.0, value).await?;
+
+    if channel
+        .create_permission(&ctx.http, &overwrite)
+        .await
+        .is_err()
+    {
+        let _: () = connection.hdel(&key, channel.id.0).await?;
+
+        return Err(LockError::Failed);
+    }
+
+    Ok(())
+}
+
+pub async fn unlock(ctx: &Context, channel: &GuildChannel) -> LockResult<()> {
+    let everyone = everyone(channel.guild_id);
+    let mut connection = database::connection().await?;
+    let key = database::guild_key(channel.guild_id.0, "locks");
+
+    let value: Option<String> = connection.hget(&key, channel.id.0).await?;
+
+    let saved = value
+        .and_then(|value| serde_json::from_str::<SavedOverwrite>(&value).ok())
+        .ok_or(LockError::NotLocked)?;
+
+    let restored = match saved.previous {
+        Some(previous) => {
+            channel
+                .create_permission(&ctx.http, &restored_overwrite(previous, everyone))
+                .await
+        }
+        None => channel.delete_permission(&ctx.http, everyone).await,
+    };
+
+    if restored.is_err() {
+        return Err(LockError::Failed);
+    }
+
+    let _: () = connection.hdel(&key, channel.id.0).await?;
+
+    Ok(())
+}
+
+pub async fn locked_channels(guild_id: GuildId) -> LockResult<Vec<u64>> {
+    let mut connection = database::connection().await?;
+
+    let channels: Vec<u64> = connection
+        .hkeys(database::guild_key(guild_id.0, "locks"))
+        .await?;
+
+    Ok(channels)
+}
+
+fn everyone(guild_id: GuildId) -> PermissionOverwriteType {
+    PermissionOverwriteType::Role(RoleId(guild_id.0))
+}
+
+// Só as permissões de enviar mudam, o resto da sobrescrita é mantido.
+fn locked_overwrite(
+    current: Option<&PermissionOverwrite>,
+    kind: PermissionOverwriteType,
+) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow: current.map_or(Permissions::empty(), |overwrite| overwrite.allow) - LOCKED,
+        deny: current.map_or(Permissions::empty(), |overwrite| overwrite.deny) | LOCKED,
+        kind,
+    }
+}
+
+fn restored_overwrite(
+    (allow, deny): (u64, u64),
+    kind: PermissionOverwriteType,
+) -> PermissionOverwrite {
+    PermissionOverwrite {
+        allow: Permissions::from_bits_truncate(allow),
+        deny: Permissions::from_bits_truncate(deny),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overwrite(allow: Permissions, deny: Permissions) -> PermissionOverwrite {
+        PermissionOverwrite {
+            allow,
+            deny,
+            kind: everyone(GuildId(1)),
+        }
+    }
+
+    #[test]
+    fn locked_overwrite_keeps_other_permissions() {
+        let current = overwrite(
+            Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES,
+            Permissions::ADD_REACTIONS,
+        );
+
+        assert_eq!(
+            locked_overwrite(Some(&current), current.kind),
+            overwrite(
+                Permissions::VIEW_CHANNEL,
+                Permissions::ADD_REACTIONS | LOCKED
+            )
+        );
+    }
+
+    #[test]
+    fn locked_overwrite_without_previous_only_denies_sending() {
+        assert_eq!(
+            locked_overwrite(None, everyone(GuildId(1))),
+            overwrite(Permissions::empty(), LOCKED)
+        );
+    }
+
+    #[test]
+    fn saved_overwrite_restores_the_original() {
+        let current = overwrite(Permissions::SEND_MESSAGES, Permissions::ATTACH_FILES);
+        let saved = SavedOverwrite {
+            previous: Some((current.allow.bits(), current.deny.bits())),
+        };
+
+        let value = serde_json::to_string(&saved).unwrap();
+        let saved = serde_json::from_str::<SavedOverwrite>(&value).unwrap();
+
+        assert_eq!(
+            restored_overwrite(saved.previous.unwrap(), current.kind),
+            current
+        );
+    }
+}
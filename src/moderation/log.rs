@@ -0,0 +1,96 @@
+use serenity::{
+    http::Http,
+    model::prelude::{ChannelId, GuildId, Timestamp, UserId},
+    prelude::{Context, Mentionable},
+};
+
+use crate::settings;
+
+const LOG_COLOR: u32 = 0xc3e2e1;
+
+pub struct LogEntry {
+    action: String,
+    actor: UserId,
+    target: Option<String>,
+    reason: Option<String>,
+    details: Vec<(String, String)>,
+}
+
+impl LogEntry {
+    pub fn new(action: impl ToString, actor: UserId) -> Self {
+        LogEntry {
+            action: action.to_string(),
+            actor,
+            target: None,
+            reason: None,
+            details: vec![],
+        }
+    }
+
+    pub fn target(mut self, target: impl ToString) -> Self {
+        self.target = Some(target.to_string());
+        self
+    }
+
+    pub fn reason(mut self, reason: Option<impl ToString>) -> Self {
+        self.reason = reason.map(|reason| reason.to_string());
+        self
+    }
+
+    pub fn detail(mut self, name: impl ToString, value: impl ToString) -> Self {
+        self.details.push((name.to_string(), value.to_string()));
+        self
+    }
+}
+
+// Falhas ao registrar não interrompem a ação, apenas avisam no canal onde o
+// comando foi usado.
+pub async fn record(ctx: &Context, guild_id: GuildId, origin: ChannelId, entry: LogEntry) {
+    deliver(&ctx.http, guild_id, origin, entry).await
+}
+
+pub async fn deliver(http: &Http, guild_id: GuildId, origin: ChannelId, entry: LogEntry) {
+    let channel_id = match settings::get(guild_id.0).await.mod_log_channel_id {
+        Some(channel_id) => ChannelId(channel_id),
+        None => return,
+    };
+
+    let sent = channel_id
+        .send_message(http, |m| {
+            m.embed(|e| {
+                e.title(&entry.action)
+                    .color(LOG_COLOR)
+                    .field("Autor", entry.actor.mention(), true)
+                    .timestamp(Timestamp::now());
+
+                if let Some(target) = &entry.target {
+                    e.field("Alvo", target, true);
+                }
+
+                e.field(
+                    "Motivo",
+                    entry.reason.clone().unwrap_or("Não informado".to_string()),
+                    false,
+                );
+
+                for (name, value) in entry.details.iter() {
+                    e.field(name, value, true);
+                }
+
+                e
+            })
+        })
+        .await;
+
+    if sent.is_err() {
+        let _ = origin
+            .say(
+                http,
+                format!(
+                    "Não foi possível registrar a ação no canal de log {}.",
+                    channel_id.mention()
+                ),
+            )
+            .await;
+    }
+}
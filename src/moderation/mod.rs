@@ -0,0 +1,1381 @@
+use std::time::Duration;
+
+use chrono::Utc;
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandError, CommandResult,
+    },
+    model::{
+        event::MessageUpdateEvent,
+        prelude::{
+            ChannelId, GuildChannel, GuildId, Member, Message, RoleId, Timestamp, User, UserId,
+        },
+    },
+    prelude::{Context, Mentionable},
+    utils::{parse_channel, parse_role, parse_username, MessageBuilder},
+};
+
+use crate::scheduler::{self, Job};
+use crate::settings;
+
+pub use self::automod::AutomodSettings;
+use self::automod::{AutomodAction, RuleKind};
+use self::blocklist::EntryKind;
+use self::infraction::{InfractionError, InfractionKind};
+use self::locks::LockError;
+pub use self::log::{deliver, record, LogEntry};
+
+mod automod;
+mod blocklist;
+mod infraction;
+mod locks;
+mod log;
+
+const MAX_TIMEOUT: u64 = 28 * 24 * 60 * 60;
+const MAX_TEMPORARY: u64 = 365 * 24 * 60 * 60;
+const MAX_AUDIT_REASON_LENGTH: usize = 512;
+const MAX_SLOWMODE: u64 = 6 * 60 * 60;
+const INFRACTIONS_SHOWN: usize = 15;
+const SCHEDULE_FAILED: &str =
+    "Não foi possível agendar o fim da punição, ela precisará ser removida manualmente.";
+
+#[group]
+#[commands(
+    modlog,
+    automod,
+    blocklist,
+    muterole,
+    warn,
+    timeout,
+    mute,
+    unmute,
+    kick,
+    ban,
+    lock,
+    unlock,
+    lockdown,
+    slowmode,
+    tempban,
+    unban,
+    infractions,
+    reason
+)]
+pub struct Moderation;
+
+#[derive(Debug)]
+pub enum ModerationError {
+    InvalidUser,
+    NotMember,
+    SelfTarget,
+    BotTarget,
+    Owner,
+    Hierarchy,
+    BotHierarchy,
+    InvalidDuration(u64),
+    MuteRoleMissing,
+    Failed,
+    Infraction(InfractionError),
+}
+
+impl From<InfractionError> for ModerationError {
+    fn from(error: InfractionError) -> Self {
+        ModerationError::Infraction(error)
+    }
+}
+
+impl std::fmt::Display for ModerationError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ModerationError::InvalidUser => write!(f, "Usuário inválido, use uma menção ou um ID."),
+            ModerationError::NotMember => write!(f, "Esse usuário não está no servidor."),
+            ModerationError::SelfTarget => write!(f, "Você não pode usar isso em si mesmo."),
+            ModerationError::BotTarget => write!(f, "Não posso usar isso em mim mesmo."),
+            ModerationError::Owner => write!(f, "Não é possível punir o dono do servidor."),
+            ModerationError::Hierarchy => {
+                write!(f, "Esse membro tem um cargo igual ou maior que o seu.")
+            }
+            ModerationError::BotHierarchy => {
+                write!(f, "Esse membro tem um cargo igual ou maior que o meu.")
+            }
+            ModerationError::InvalidDuration(max) => write!(
+                f,
+                "A duração deve ser entre 1 segundo e {} dias, como `10m` ou `2h`.",
+                max / (24 * 60 * 60)
+            ),
+            ModerationError::MuteRoleMissing => {
+                write!(
+                    f,
+                    "Nenhum cargo de silenciado definido, use `!muterole @cargo`."
+                )
+            }
+            ModerationError::Failed => write!(
+                f,
+                "Não foi possível concluir a ação, verifique as minhas permissões."
+            ),
+            ModerationError::Infraction(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+pub async fn scan(ctx: &Context, msg: &Message) {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return,
+    };
+
+    let roles = msg
+        .member
+        .as_ref()
+        .map(|member| member.roles.clone())
+        .unwrap_or_default();
+
+    let blocked = blocklist::check(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        msg.id,
+        &msg.author,
+        &roles,
+        &msg.content,
+    )
+    .await;
+
+    if !blocked {
+        automod::check(ctx, msg).await;
+    }
+}
+
+pub async fn scan_edit(ctx: &Context, event: &MessageUpdateEvent) {
+    let (guild_id, author, content) = match (event.guild_id, &event.author, &event.content) {
+        (Some(guild_id), Some(author), Some(content)) => (guild_id, author, content),
+        _ => return,
+    };
+
+    let roles = ctx
+        .cache
+        .member_field(guild_id, author.id, |member| member.roles.clone())
+        .unwrap_or_default();
+
+    blocklist::check(
+        ctx,
+        guild_id,
+        event.channel_id,
+        event.id,
+        author,
+        &roles,
+        content,
+    )
+    .await;
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn modlog(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let channel_id = match args.rest().trim() {
+        "off" => None,
+        value => match parse_channel(value).or_else(|| value.parse::<u64>().ok()) {
+            Some(channel_id) => Some(ChannelId(channel_id)),
+            None => {
+                let content = match settings::get(guild_id.0).await.mod_log_channel_id {
+                    Some(channel_id) => format!(
+                        "As ações de moderação são registradas em {}.",
+                        ChannelId(channel_id).mention()
+                    ),
+                    None => "Nenhum canal de log definido, use `!modlog #canal`.".to_string(),
+                };
+
+                msg.reply(&ctx.http, content).await?;
+
+                return Ok(());
+            }
+        },
+    };
+
+    let updated = settings::update(guild_id.0, |s| {
+        s.mod_log_channel_id = channel_id.map(|channel_id| channel_id.0)
+    })
+    .await;
+
+    if let Err(why) = updated {
+        msg.reply(
+            &ctx.http,
+            format!("Não foi possível salvar a configuração: {why}."),
+        )
+        .await?;
+
+        return Ok(());
+    }
+
+    let content = match channel_id {
+        Some(channel_id) => format!(
+            "As ações de moderação serão registradas em {}.",
+            channel_id.mention()
+        ),
+        None => "Canal de log removido.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new("Canal de log alterado", msg.author.id).detail(
+            "Canal",
+            channel_id.map_or("Nenhum".to_string(), |channel_id| {
+                channel_id.mention().to_string()
+            }),
+        ),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn warn(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let prepared = prepare(ctx, msg, &mut args, false).await;
+
+    let (user, reason) = match prepared {
+        Ok((user, _, reason)) => (user, reason),
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    notify(ctx, guild_id, &user, InfractionKind::Warn, &reason).await;
+
+    conclude(ctx, msg, InfractionKind::Warn, &user, reason, None).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn timeout(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let prepared = prepare(ctx, msg, &mut args, true).await;
+
+    let (user, rest) = match prepared {
+        Ok((user, _, rest)) => (user, rest),
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    let (seconds, reason) = match parse_duration(&rest, MAX_TIMEOUT) {
+        Some(parsed) => parsed,
+        None => return fail(ctx, msg, ModerationError::InvalidDuration(MAX_TIMEOUT)).await,
+    };
+
+    let until = Timestamp::from_unix_timestamp(Utc::now().timestamp() + seconds as i64).unwrap();
+
+    let result = guild_id
+        .edit_member(&ctx.http, user.id, |m| {
+            m.disable_communication_until_datetime(until)
+        })
+        .await;
+
+    if result.is_err() {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    notify(ctx, guild_id, &user, InfractionKind::Timeout, &reason).await;
+
+    conclude(
+        ctx,
+        msg,
+        InfractionKind::Timeout,
+        &user,
+        reason,
+        Some(seconds),
+    )
+    .await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn automod(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let kind = RuleKind::parse(&args.single::<String>().unwrap_or_default().to_lowercase());
+    let option = args.single::<String>().unwrap_or_default().to_lowercase();
+    let value = args.rest().trim().to_lowercase();
+
+    let automod = settings::get(guild_id).await.automod;
+
+    let kind = match kind {
+        Some(kind) => kind,
+        None => {
+            msg.channel_id
+                .say(&ctx.http, automod_status(&automod))
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let mut rule = automod.rule(kind).clone();
+
+    let content = match option.as_str() {
+        "on" | "off" => {
+            rule.enabled = option == "on";
+
+            match rule.enabled {
+                true => format!("Regra `{}` ativada.", kind.command_name()),
+                false => format!("Regra `{}` desativada.", kind.command_name()),
+            }
+        }
+        "action" if AutomodAction::parse(&value).is_some() => {
+            rule.action = AutomodAction::parse(&value).unwrap();
+
+            format!(
+                "A regra `{}` vai {} a mensagem.",
+                kind.command_name(),
+                rule.action.name()
+            )
+        }
+        "limit" if automod_limit(kind, &value).is_some() => {
+            rule.limit = automod_limit(kind, &value);
+
+            format!(
+                "Limite da regra `{}`: {}.",
+                kind.command_name(),
+                kind.limit_description(rule.limit.unwrap())
+            )
+        }
+        "exempt" if parse_role(&value).is_some() => {
+            let role_id = parse_role(&value).unwrap();
+
+            match rule.exempt_roles.iter().position(|id| *id == role_id) {
+                Some(index) => {
+                    rule.exempt_roles.remove(index);
+
+                    format!(
+                        "O cargo <@&{role_id}> não está mais isento da regra `{}`.",
+                        kind.command_name()
+                    )
+                }
+                None => {
+                    rule.exempt_roles.push(role_id);
+
+                    format!(
+                        "O cargo <@&{role_id}> está isento da regra `{}`.",
+                        kind.command_name()
+                    )
+                }
+            }
+        }
+        "exempt" if parse_channel(&value).is_some() => {
+            let channel_id = parse_channel(&value).unwrap();
+
+            match rule.exempt_channels.iter().position(|id| *id == channel_id) {
+                Some(index) => {
+                    rule.exempt_channels.remove(index);
+
+                    format!(
+                        "O canal <#{channel_id}> não está mais isento da regra `{}`.",
+                        kind.command_name()
+                    )
+                }
+                None => {
+                    rule.exempt_channels.push(channel_id);
+
+                    format!(
+                        "O canal <#{channel_id}> está isento da regra `{}`.",
+                        kind.command_name()
+                    )
+                }
+            }
+        }
+        _ => {
+            msg.reply(
+                &ctx.http,
+                "Use `!automod <regra> <on|off|action <delete|warn|timeout>|limit <n>|exempt <@cargo|#canal>>`.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id, |s| *s.automod.rule_mut(kind) = rule).await {
+        Ok(_) => content,
+        Err(_) => "Não foi possível salvar a configuração.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+fn automod_limit(kind: RuleKind, value: &str) -> Option<u32> {
+    let range = match kind {
+        RuleKind::Invites => return None,
+        RuleKind::Caps => 50..=100,
+        RuleKind::Mentions => 2..=50,
+        RuleKind::Spam | RuleKind::Duplicates => 2..=20,
+    };
+
+    value
+        .parse::<u32>()
+        .ok()
+        .filter(|limit| range.contains(limit))
+}
+
+fn automod_status(automod: &AutomodSettings) -> String {
+    let mut content = MessageBuilder::new();
+
+    content.push_bold_line("Automod");
+
+    for kind in RuleKind::ALL {
+        let rule = automod.rule(kind);
+
+        content.push(format!(
+            "`{}` {} — {} — {}",
+            kind.command_name(),
+            if rule.enabled {
+                "ativada"
+            } else {
+                "desativada"
+            },
+            kind.limit_description(automod.limit(kind)),
+            rule.action.name()
+        ));
+
+        let exempt = rule
+            .exempt_roles
+            .iter()
+            .map(|role_id| format!("<@&{role_id}>"))
+            .chain(
+                rule.exempt_channels
+                    .iter()
+                    .map(|channel_id| format!("<#{channel_id}>")),
+            )
+            .collect::<Vec<String>>();
+
+        if !exempt.is_empty() {
+            content.push(format!(" — isentos: {}", exempt.join(", ")));
+        }
+
+        content.push("\n");
+    }
+
+    content.push("Use `!automod <regra> <on|off|action|limit|exempt>` para configurar.");
+
+    content.build()
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn blocklist(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+    let rest = args.rest().trim();
+
+    let (kind, pattern) = match rest.strip_prefix("--regex") {
+        Some(pattern) => (EntryKind::Regex, pattern.trim()),
+        None => (EntryKind::Word, rest),
+    };
+
+    let content = match action.as_str() {
+        "add" if !pattern.is_empty() => match blocklist::add(guild_id, kind, pattern).await {
+            Ok(entry) => format!("{} adicionado à lista de bloqueio.", entry.name()),
+            Err(why) => why.to_string(),
+        },
+        "remove" if !pattern.is_empty() => match blocklist::remove(guild_id, kind, pattern).await {
+            Ok(_) => "Termo removido da lista de bloqueio.".to_string(),
+            Err(why) => why.to_string(),
+        },
+        "list" => match blocklist::list(guild_id).await {
+            Ok(entries) if entries.is_empty() => "A lista de bloqueio está vazia.".to_string(),
+            Ok(entries) => {
+                let mut content = MessageBuilder::new();
+
+                content.push_bold_line(format!("Lista de bloqueio ({})", entries.len()));
+
+                for entry in entries {
+                    content.push_line(entry.name());
+                }
+
+                content.build()
+            }
+            Err(why) => why.to_string(),
+        },
+        "test" if !rest.is_empty() => match blocklist::test(guild_id, rest).await {
+            Ok(found) if found.is_empty() => "Nenhum termo bloqueado encontrado.".to_string(),
+            Ok(found) => format!(
+                "Termos encontrados: {}.",
+                found
+                    .iter()
+                    .map(|entry| entry.name())
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Err(why) => why.to_string(),
+        },
+        _ => "Use `!blocklist <add|remove> [--regex] <termo>`, `!blocklist list` ou `!blocklist test <texto>`.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn muterole(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let role_id = match msg.mention_roles.first() {
+        Some(role_id) => Some(role_id.0),
+        None if args.rest().trim() == "off" => None,
+        None => {
+            let content = match settings::get(guild_id.0).await.mute_role_id {
+                Some(role_id) => format!("O cargo de silenciado é <@&{role_id}>."),
+                None => ModerationError::MuteRoleMissing.to_string(),
+            };
+
+            msg.reply(&ctx.http, content).await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id.0, |s| s.mute_role_id = role_id).await {
+        Ok(_) if role_id.is_some() => "Cargo de silenciado definido.",
+        Ok(_) => "Cargo de silenciado removido.",
+        Err(_) => "Não foi possível salvar a configuração.",
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn mute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let role_id = match settings::get(guild_id.0).await.mute_role_id {
+        Some(role_id) => role_id,
+        None => return fail(ctx, msg, ModerationError::MuteRoleMissing).await,
+    };
+
+    let prepared = prepare(ctx, msg, &mut args, true).await;
+
+    let (user, rest) = match prepared {
+        Ok((user, _, rest)) => (user, rest),
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    // A duração é opcional, sem ela o silenciamento só termina com `!unmute`.
+    let (duration, reason) = match parse_duration(&rest, MAX_TEMPORARY) {
+        Some((seconds, reason)) => (Some(seconds), reason),
+        None => (None, rest),
+    };
+
+    let added = ctx
+        .http
+        .add_member_role(
+            guild_id.0,
+            user.id.0,
+            role_id,
+            Some(&audit_reason(msg, &reason)),
+        )
+        .await;
+
+    if added.is_err() {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    let id = scheduler::unmute_id(guild_id.0, user.id.0);
+
+    let scheduled = match duration {
+        Some(seconds) => {
+            let job = Job::Unmute {
+                guild_id: guild_id.0,
+                user_id: user.id.0,
+                role_id,
+                channel_id: msg.channel_id.0,
+            };
+
+            scheduler::schedule(&id, job, Utc::now().timestamp() + seconds as i64).await
+        }
+        None => scheduler::cancel(&id).await,
+    };
+
+    if scheduled.is_err() {
+        msg.reply(&ctx.http, SCHEDULE_FAILED).await?;
+    }
+
+    notify(ctx, guild_id, &user, InfractionKind::Mute, &reason).await;
+
+    conclude(ctx, msg, InfractionKind::Mute, &user, reason, duration).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn unmute(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let role_id = match settings::get(guild_id.0).await.mute_role_id {
+        Some(role_id) => role_id,
+        None => return fail(ctx, msg, ModerationError::MuteRoleMissing).await,
+    };
+
+    let prepared = prepare(ctx, msg, &mut args, true).await;
+
+    let (user, reason) = match prepared {
+        Ok((user, _, reason)) => (user, reason),
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    let removed = ctx
+        .http
+        .remove_member_role(
+            guild_id.0,
+            user.id.0,
+            role_id,
+            Some(&audit_reason(msg, &reason)),
+        )
+        .await;
+
+    if removed.is_err() {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    let _ = scheduler::cancel(&scheduler::unmute_id(guild_id.0, user.id.0)).await;
+
+    conclude(ctx, msg, InfractionKind::Unmute, &user, reason, None).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn kick(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let prepared = prepare(ctx, msg, &mut args, true).await;
+
+    let (user, reason) = match prepared {
+        Ok((user, Some(_), reason)) => (user, reason),
+        Ok(_) => return fail(ctx, msg, ModerationError::NotMember).await,
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    // O aviso precisa ser enviado antes, depois o bot não consegue mais falar
+    // com quem saiu do servidor.
+    notify(ctx, guild_id, &user, InfractionKind::Kick, &reason).await;
+
+    let result = guild_id
+        .kick_with_reason(&ctx.http, user.id, &audit_reason(msg, &reason))
+        .await;
+
+    if result.is_err() {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    conclude(ctx, msg, InfractionKind::Kick, &user, reason, None).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn ban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    ban_user(ctx, msg, &mut args, false).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn tempban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    ban_user(ctx, msg, &mut args, true).await
+}
+
+async fn ban_user(ctx: &Context, msg: &Message, args: &mut Args, temporary: bool) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let prepared = prepare(ctx, msg, args, false).await;
+
+    let (user, member, reason) = match prepared {
+        Ok(prepared) => prepared,
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    let (duration, reason) = match parse_duration(&reason, MAX_TEMPORARY) {
+        Some((seconds, reason)) if temporary => (Some(seconds), reason),
+        _ if temporary => {
+            return fail(ctx, msg, ModerationError::InvalidDuration(MAX_TEMPORARY)).await
+        }
+        _ => (None, reason),
+    };
+
+    if member.is_some() {
+        notify(ctx, guild_id, &user, InfractionKind::Ban, &reason).await;
+    }
+
+    let result = guild_id
+        .ban_with_reason(&ctx.http, user.id, 0, audit_reason(msg, &reason))
+        .await;
+
+    if result.is_err() {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    let id = scheduler::unban_id(guild_id.0, user.id.0);
+
+    let scheduled = match duration {
+        Some(seconds) => {
+            let job = Job::Unban {
+                guild_id: guild_id.0,
+                user_id: user.id.0,
+                channel_id: msg.channel_id.0,
+            };
+
+            scheduler::schedule(&id, job, Utc::now().timestamp() + seconds as i64).await
+        }
+        None => scheduler::cancel(&id).await,
+    };
+
+    if scheduled.is_err() {
+        msg.reply(&ctx.http, SCHEDULE_FAILED).await?;
+    }
+
+    conclude(ctx, msg, InfractionKind::Ban, &user, reason, duration).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn unban(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let user = match target(ctx, &mut args).await {
+        Ok(user) => user,
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    let reason = rest(&args);
+
+    if let Err(why) = infraction::check_reason(reason.as_deref()) {
+        return fail(ctx, msg, why.into()).await;
+    }
+
+    if guild_id.unban(&ctx.http, user.id).await.is_err() {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    let _ = scheduler::cancel(&scheduler::unban_id(guild_id.0, user.id.0)).await;
+
+    conclude(ctx, msg, InfractionKind::Unban, &user, reason, None).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn infractions(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let user = match target(ctx, &mut args).await {
+        Ok(user) => user,
+        Err(why) => return fail(ctx, msg, why).await,
+    };
+
+    let infractions = match infraction::list(guild_id.0, user.id.0).await {
+        Ok(infractions) => infractions,
+        Err(why) => return fail(ctx, msg, why.into()).await,
+    };
+
+    if infractions.is_empty() {
+        msg.reply(&ctx.http, format!("{} não tem nenhum caso.", user.tag()))
+            .await?;
+
+        return Ok(());
+    }
+
+    let mut content = MessageBuilder::new();
+
+    content.push_bold_line_safe(format!(
+        "Histórico de {} ({} casos)",
+        user.tag(),
+        infractions.len()
+    ));
+
+    for infraction in infractions.iter().rev().take(INFRACTIONS_SHOWN) {
+        let reason = infraction
+            .reason
+            .clone()
+            .unwrap_or("Não informado".to_string())
+            .chars()
+            .take(80)
+            .collect::<String>();
+
+        content
+            .push(format!(
+                "`#{}` {} <t:{}:d> por <@{}>",
+                infraction.case,
+                infraction.kind.name(),
+                infraction.created_at,
+                infraction.moderator_id
+            ))
+            .push(" — ")
+            .push_line_safe(reason);
+    }
+
+    if infractions.len() > INFRACTIONS_SHOWN {
+        content.push_italic(format!(
+            "Mostrando os {INFRACTIONS_SHOWN} casos mais recentes."
+        ));
+    }
+
+    msg.channel_id.say(&ctx.http, content.build()).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn reason(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let case = args.single::<String>().unwrap_or_default();
+    let case = case.trim_start_matches('#').parse::<u64>();
+    let reason = rest(&args);
+
+    let (case, reason) = match (case, reason) {
+        (Ok(case), Some(reason)) => (case, reason),
+        _ => {
+            msg.reply(&ctx.http, "Use `!reason <caso> <motivo>`.")
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let previous = match infraction::get(guild_id.0, case).await {
+        Ok(infraction) => infraction.reason,
+        Err(why) => return fail(ctx, msg, why.into()).await,
+    };
+
+    let infraction = match infraction::edit_reason(guild_id.0, case, reason).await {
+        Ok(infraction) => infraction,
+        Err(why) => return fail(ctx, msg, why.into()).await,
+    };
+
+    msg.reply(&ctx.http, format!("Motivo do caso #{case} atualizado."))
+        .await?;
+
+    record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new(format!("Motivo alterado | Caso #{case}"), msg.author.id)
+            .target(UserId(infraction.user_id).mention())
+            .reason(infraction.reason)
+            .detail(
+                "Motivo anterior",
+                previous.unwrap_or("Não informado".to_string()),
+            ),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn lock(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let channel = match target_channel(ctx, msg, &mut args).await {
+        Some(channel) if locks::is_lockable(&channel) => channel,
+        _ => {
+            msg.reply(
+                &ctx.http,
+                "Use `!lock [#canal] [motivo]` em um canal de texto.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let reason = rest(&args);
+
+    if let Err(why) = locks::lock(ctx, &channel).await {
+        msg.reply(&ctx.http, why.to_string()).await?;
+
+        return Err(CommandError::from("Ocorreu um erro."));
+    }
+
+    let _ = channel
+        .say(
+            &ctx.http,
+            lock_notice("Canal bloqueado pela moderação.", &reason),
+        )
+        .await;
+
+    if channel.id != msg.channel_id {
+        msg.reply(&ctx.http, format!("{} bloqueado.", channel.mention()))
+            .await?;
+    }
+
+    record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new("Canal bloqueado", msg.author.id)
+            .target(channel.mention())
+            .reason(reason),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn unlock(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let channel = match target_channel(ctx, msg, &mut args).await {
+        Some(channel) if locks::is_lockable(&channel) => channel,
+        _ => {
+            msg.reply(
+                &ctx.http,
+                "Use `!unlock [#canal] [motivo]` em um canal de texto.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let reason = rest(&args);
+
+    if let Err(why) = locks::unlock(ctx, &channel).await {
+        msg.reply(&ctx.http, why.to_string()).await?;
+
+        return Err(CommandError::from("Ocorreu um erro."));
+    }
+
+    let _ = channel
+        .say(&ctx.http, lock_notice("Canal desbloqueado.", &reason))
+        .await;
+
+    if channel.id != msg.channel_id {
+        msg.reply(&ctx.http, format!("{} desbloqueado.", channel.mention()))
+            .await?;
+    }
+
+    record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new("Canal desbloqueado", msg.author.id)
+            .target(channel.mention())
+            .reason(reason),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn lockdown(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let lifting = args
+        .current()
+        .is_some_and(|value| value.eq_ignore_ascii_case("off"));
+
+    if lifting {
+        args.advance();
+    }
+
+    let reason = rest(&args);
+
+    let channels = match guild_id.channels(&ctx.http).await {
+        Ok(channels) => channels,
+        Err(_) => return fail(ctx, msg, ModerationError::Failed).await,
+    };
+
+    let targets = match lifting {
+        true => match locks::locked_channels(guild_id).await {
+            Ok(locked) => locked
+                .into_iter()
+                .filter_map(|channel_id| channels.get(&ChannelId(channel_id)).cloned())
+                .collect::<Vec<GuildChannel>>(),
+            Err(why) => {
+                msg.reply(&ctx.http, why.to_string()).await?;
+
+                return Err(CommandError::from("Ocorreu um erro."));
+            }
+        },
+        false => channels
+            .into_values()
+            .filter(locks::is_lockable)
+            .collect::<Vec<GuildChannel>>(),
+    };
+
+    let mut changed = 0;
+    let mut failed = 0;
+
+    for channel in targets.iter() {
+        let result = match lifting {
+            true => locks::unlock(ctx, channel).await,
+            false => locks::lock(ctx, channel).await,
+        };
+
+        let notice = match lifting {
+            true => "Fim do bloqueio geral, o canal foi desbloqueado.",
+            false => "Servidor em bloqueio geral.",
+        };
+
+        match result {
+            Ok(_) => {
+                changed += 1;
+
+                let _ = channel.say(&ctx.http, lock_notice(notice, &reason)).await;
+            }
+            Err(LockError::AlreadyLocked) => {}
+            Err(_) => failed += 1,
+        }
+    }
+
+    let mut content = match lifting {
+        true => format!("Bloqueio geral encerrado, {changed} canais desbloqueados."),
+        false => format!("Bloqueio geral ativado, {changed} canais bloqueados."),
+    };
+
+    if failed > 0 {
+        content.push_str(&format!("\n{failed} canais não puderam ser alterados."));
+    }
+
+    msg.reply(&ctx.http, content).await?;
+
+    let action = match lifting {
+        true => "Bloqueio geral encerrado",
+        false => "Bloqueio geral ativado",
+    };
+
+    record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new(action, msg.author.id)
+            .reason(reason)
+            .detail("Canais", changed)
+            .detail("Falhas", failed),
+    )
+    .await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn slowmode(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let channel = target_channel(ctx, msg, &mut args).await;
+    let value = args.rest().trim().to_lowercase();
+
+    let seconds = match value.as_str() {
+        "off" | "0" => Some(0),
+        value => humantime::parse_duration(value)
+            .ok()
+            .map(|duration| duration.as_secs())
+            .filter(|seconds| *seconds > 0 && *seconds <= MAX_SLOWMODE),
+    };
+
+    let (mut channel, seconds) = match (channel, seconds) {
+        (Some(channel), Some(seconds)) if locks::is_lockable(&channel) => (channel, seconds),
+        _ => {
+            msg.reply(
+                &ctx.http,
+                "Use `!slowmode [#canal] <duração|off>`, com até 6 horas.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    if channel
+        .edit(ctx, |c| c.rate_limit_per_user(seconds))
+        .await
+        .is_err()
+    {
+        return fail(ctx, msg, ModerationError::Failed).await;
+    }
+
+    let notice = match seconds {
+        0 => "Modo lento desativado.".to_string(),
+        seconds => format!(
+            "Modo lento ativado: uma mensagem a cada {}.",
+            humantime::format_duration(Duration::from_secs(seconds))
+        ),
+    };
+
+    let _ = channel.say(&ctx.http, &notice).await;
+
+    if channel.id != msg.channel_id {
+        msg.reply(&ctx.http, format!("{}: {notice}", channel.mention()))
+            .await?;
+    }
+
+    record(
+        ctx,
+        guild_id,
+        msg.channel_id,
+        LogEntry::new("Modo lento alterado", msg.author.id)
+            .target(channel.mention())
+            .detail(
+                "Intervalo",
+                match seconds {
+                    0 => "Desativado".to_string(),
+                    seconds => humantime::format_duration(Duration::from_secs(seconds)).to_string(),
+                },
+            ),
+    )
+    .await;
+
+    Ok(())
+}
+
+async fn target_channel(ctx: &Context, msg: &Message, args: &mut Args) -> Option<GuildChannel> {
+    let channel_id = match args.current().and_then(parse_channel) {
+        Some(channel_id) => {
+            args.advance();
+
+            ChannelId(channel_id)
+        }
+        None => msg.channel_id,
+    };
+
+    channel_id
+        .to_channel(ctx)
+        .await
+        .ok()
+        .and_then(|channel| channel.guild())
+        .filter(|channel| Some(channel.guild_id) == msg.guild_id)
+}
+
+fn lock_notice(notice: &str, reason: &Option<String>) -> String {
+    match reason {
+        Some(reason) => format!("{notice}\nMotivo: {reason}"),
+        None => notice.to_string(),
+    }
+}
+
+async fn fail(ctx: &Context, msg: &Message, why: ModerationError) -> CommandResult {
+    msg.reply(&ctx.http, why.to_string()).await?;
+
+    Err(CommandError::from("Ocorreu um erro."))
+}
+
+fn rest(args: &Args) -> Option<String> {
+    Some(args.rest().trim().to_string()).filter(|rest| !rest.is_empty())
+}
+
+// Separa a duração do começo do texto, o restante é o motivo.
+fn parse_duration(value: &Option<String>, max: u64) -> Option<(u64, Option<String>)> {
+    let value = value.as_deref()?;
+    let (duration, reason) = value.split_once(' ').unwrap_or((value, ""));
+
+    let seconds = humantime::parse_duration(duration)
+        .ok()
+        .map(|duration| duration.as_secs())
+        .filter(|seconds| *seconds > 0 && *seconds <= max)?;
+
+    let reason = Some(reason.trim().to_string()).filter(|reason| !reason.is_empty());
+
+    Some((seconds, reason))
+}
+
+async fn target(ctx: &Context, args: &mut Args) -> Result<User, ModerationError> {
+    let value = args.single::<String>().unwrap_or_default();
+
+    let user_id = parse_username(&value)
+        .or_else(|| value.parse::<u64>().ok())
+        .ok_or(ModerationError::InvalidUser)?;
+
+    UserId(user_id)
+        .to_user(ctx)
+        .await
+        .map_err(|_| ModerationError::InvalidUser)
+}
+
+async fn prepare(
+    ctx: &Context,
+    msg: &Message,
+    args: &mut Args,
+    require_member: bool,
+) -> Result<(User, Option<Member>, Option<String>), ModerationError> {
+    let guild_id = msg.guild_id.unwrap();
+
+    let user = target(ctx, args).await?;
+    let reason = rest(args);
+
+    infraction::check_reason(reason.as_deref())?;
+
+    let member = check_hierarchy(ctx, guild_id, msg.author.id, user.id).await?;
+
+    if require_member && member.is_none() {
+        return Err(ModerationError::NotMember);
+    }
+
+    Ok((user, member, reason))
+}
+
+// Só é possível agir sobre membros com cargo abaixo do moderador e do bot.
+async fn check_hierarchy(
+    ctx: &Context,
+    guild_id: GuildId,
+    moderator_id: UserId,
+    user_id: UserId,
+) -> Result<Option<Member>, ModerationError> {
+    let bot_id = ctx.cache.current_user_id();
+    let owner_id = ctx.cache.guild_field(guild_id, |g| g.owner_id);
+
+    if user_id == moderator_id {
+        return Err(ModerationError::SelfTarget);
+    }
+
+    if user_id == bot_id {
+        return Err(ModerationError::BotTarget);
+    }
+
+    if Some(user_id) == owner_id {
+        return Err(ModerationError::Owner);
+    }
+
+    let member = match guild_id.member(ctx, user_id).await {
+        Ok(member) => member,
+        Err(_) => return Ok(None),
+    };
+
+    let position = |member: &Member| {
+        member
+            .highest_role_info(&ctx.cache)
+            .map_or(0, |(_, position)| position)
+    };
+
+    if Some(moderator_id) != owner_id {
+        let moderator = guild_id
+            .member(ctx, moderator_id)
+            .await
+            .map_err(|_| ModerationError::Failed)?;
+
+        if position(&moderator) <= position(&member) {
+            return Err(ModerationError::Hierarchy);
+        }
+    }
+
+    let bot = guild_id
+        .member(ctx, bot_id)
+        .await
+        .map_err(|_| ModerationError::Failed)?;
+
+    if position(&bot) <= position(&member) {
+        return Err(ModerationError::BotHierarchy);
+    }
+
+    Ok(Some(member))
+}
+
+fn audit_reason(msg: &Message, reason: &Option<String>) -> String {
+    let audit_reason = match reason {
+        Some(reason) => format!("{}: {reason}", msg.author.tag()),
+        None => msg.author.tag(),
+    };
+
+    audit_reason.chars().take(MAX_AUDIT_REASON_LENGTH).collect()
+}
+
+// Moderadores e o dono do servidor não passam pelos filtros automáticos.
+fn is_moderator(ctx: &Context, guild_id: GuildId, user_id: UserId, roles: &[RoleId]) -> bool {
+    ctx.cache
+        .guild_field(guild_id, |g| {
+            g.owner_id == user_id
+                || roles.iter().any(|role_id| {
+                    g.roles.get(role_id).is_some_and(|role| {
+                        role.permissions.administrator() || role.permissions.manage_messages()
+                    })
+                })
+        })
+        .unwrap_or(false)
+}
+
+async fn notify(
+    ctx: &Context,
+    guild_id: GuildId,
+    user: &User,
+    kind: InfractionKind,
+    reason: &Option<String>,
+) {
+    let guild_name = ctx
+        .cache
+        .guild_field(guild_id, |g| g.name.clone())
+        .unwrap_or_default();
+
+    let content = format!(
+        "Você recebeu uma punição em **{guild_name}**: {}.\nMotivo: {}",
+        kind.name(),
+        reason.as_deref().unwrap_or("Não informado")
+    );
+
+    let _ = user.direct_message(ctx, |m| m.content(content)).await;
+}
+
+async fn conclude(
+    ctx: &Context,
+    msg: &Message,
+    kind: InfractionKind,
+    user: &User,
+    reason: Option<String>,
+    duration: Option<u64>,
+) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+
+    let created = infraction::create(
+        guild_id.0,
+        kind,
+        user.id.0,
+        msg.author.id.0,
+        reason.clone(),
+        duration,
+    )
+    .await;
+
+    let (action, content) = match &created {
+        Ok(infraction) => (
+            format!("{} | Caso #{}", kind.name(), infraction.case),
+            format!(
+                "{}: {} (caso #{}).",
+                kind.name(),
+                user.tag(),
+                infraction.case
+            ),
+        ),
+        Err(why) => (
+            kind.name().to_string(),
+            format!("{}: {}.\n{why}", kind.name(), user.tag()),
+        ),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    let mut entry = LogEntry::new(action, msg.author.id)
+        .target(format!("{} ({})", user.mention(), user.tag()))
+        .reason(reason);
+
+    if let Some(duration) = duration {
+        entry = entry.detail(
+            "Duração",
+            humantime::format_duration(Duration::from_secs(duration)),
+        );
+    }
+
+    record(ctx, guild_id, msg.channel_id, entry).await;
+
+    Ok(())
+}
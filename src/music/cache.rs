@@ -0,0 +1,402 @@
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use lazy_static::lazy_static;
+use redis::{AsyncCommands, RedisResult};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use songbird::input::Metadata;
+use tokio::sync::Mutex;
+
+use super::query::SearchSource;
+use crate::database;
+
+const CACHE_TTL: Duration = Duration::from_secs(60 * 60 * 24);
+const MEMORY_LIMIT: usize = 1000;
+const STREAM_TTL: i64 = 30 * 60;
+const STREAM_MARGIN: i64 = 5 * 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CachedMedia {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub duration: Option<u64>,
+    pub thumbnail: Option<String>,
+    pub url: String,
+    pub origin: SearchSource,
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub date: Option<String>,
+    #[serde(default)]
+    pub stream_url: Option<String>,
+    #[serde(default)]
+    pub stream_expires_at: Option<i64>,
+}
+
+pub struct CacheStats {
+    pub hits: usize,
+    pub misses: usize,
+    pub memory_entries: usize,
+    pub redis_entries: usize,
+}
+
+lazy_static! {
+    static ref ENTRIES: Mutex<HashMap<String, (Instant, CachedMedia)>> = Mutex::new(HashMap::new());
+}
+
+static HITS: AtomicUsize = AtomicUsize::new(0);
+static MISSES: AtomicUsize = AtomicUsize::new(0);
+
+impl CachedMedia {
+    // Transmissões ao vivo não guardam o stream, que muda durante a live.
+    pub fn from_metadata(
+        metadata: &Metadata,
+        origin: SearchSource,
+        stream_url: &str,
+    ) -> Option<Self> {
+        let stream_url = metadata.duration.map(|_| stream_url.to_string());
+        let stream_expires_at = stream_url.as_deref().map(stream_expiry);
+
+        Some(CachedMedia {
+            title: metadata.title.clone(),
+            artist: metadata.artist.clone().or(metadata.channel.clone()),
+            duration: metadata.duration.map(|duration| duration.as_secs()),
+            thumbnail: metadata.thumbnail.clone(),
+            url: metadata.source_url.clone()?,
+            origin,
+            channel: metadata.channel.clone(),
+            date: metadata.date.clone(),
+            stream_url,
+            stream_expires_at,
+        })
+    }
+
+    pub fn metadata(&self) -> Metadata {
+        Metadata {
+            title: self.title.clone(),
+            artist: self.artist.clone(),
+            thumbnail: self.thumbnail.clone(),
+            duration: self.duration.map(Duration::from_secs),
+            source_url: Some(self.url.clone()),
+            channel: self.channel.clone().or(self.artist.clone()),
+            date: self.date.clone(),
+            ..Metadata::default()
+        }
+    }
+
+    // O stream precisa continuar válido até o fim da música.
+    pub fn valid_stream(&self) -> Option<&str> {
+        let expires_at = self.stream_expires_at?;
+        let needed = self.duration? as i64 + STREAM_MARGIN;
+
+        if expires_at - Utc::now().timestamp() < needed {
+            return None;
+        }
+
+        self.stream_url.as_deref()
+    }
+}
+
+// URLs do YouTube trazem o vencimento no parâmetro `expire`; para as outras
+// fontes vale um prazo curto.
+fn stream_expiry(stream_url: &str) -> i64 {
+    let fallback = Utc::now().timestamp() + STREAM_TTL;
+
+    Url::parse(stream_url)
+        .ok()
+        .and_then(|url| {
+            url.query_pairs()
+                .find(|(name, _)| name == "expire")
+                .and_then(|(_, value)| value.parse::<i64>().ok())
+        })
+        .unwrap_or(fallback)
+}
+
+pub fn search_key(source: SearchSource, terms: &str) -> String {
+    let terms = terms
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+        .to_lowercase();
+
+    format!("search:{}:{}", source.prefix(), terms)
+}
+
+pub fn url_key(url: &str) -> String {
+    format!("url:{}", normalize_url(url))
+}
+
+pub fn normalize_url(url: &str) -> String {
+    let mut url = match Url::parse(url.trim()) {
+        Ok(url) => url,
+        Err(_) => return url.trim().to_string(),
+    };
+
+    let host = url.host_str().unwrap_or_default().to_lowercase();
+    let host = host
+        .trim_start_matches("www.")
+        .trim_start_matches("m.")
+        .to_string();
+
+    if host == "youtu.be" {
+        let id = url.path().trim_start_matches('/').to_string();
+
+        return format!("https://youtube.com/watch?v={id}");
+    }
+
+    if host == "youtube.com" || host == "music.youtube.com" {
+        let id = url
+            .query_pairs()
+            .find(|(name, _)| name == "v")
+            .map(|(_, value)| value.to_string());
+
+        if let Some(id) = id {
+            return format!("https://{host}/watch?v={id}");
+        }
+    }
+
+    let query = url
+        .query_pairs()
+        .filter(|(name, _)| !name.starts_with("utm_") && name != "si" && name != "feature")
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect::<Vec<(String, String)>>();
+
+    url.set_fragment(None);
+    url.set_query(None);
+
+    if !query.is_empty() {
+        url.query_pairs_mut().extend_pairs(query);
+    }
+
+    let _ = url.set_host(Some(&host));
+    let _ = url.set_scheme("https");
+
+    url.to_string().trim_end_matches('/').to_string()
+}
+
+pub async fn get(key: &str) -> Option<CachedMedia> {
+    let cached = {
+        let mut entries = ENTRIES.lock().await;
+
+        match entries.get(key) {
+            Some((stored_at, media)) if stored_at.elapsed() < CACHE_TTL => Some(media.clone()),
+            Some(_) => {
+                entries.remove(key);
+                None
+            }
+            None => None,
+        }
+    };
+
+    let cached = match cached {
+        Some(media) => Some(media),
+        None => load(key).await,
+    };
+
+    match cached {
+        Some(media) => {
+            HITS.fetch_add(1, Ordering::Relaxed);
+
+            remember(key, media.clone()).await;
+
+            Some(media)
+        }
+        None => {
+            MISSES.fetch_add(1, Ordering::Relaxed);
+
+            None
+        }
+    }
+}
+
+pub async fn store(keys: &[String], media: CachedMedia) {
+    let value = serde_json::to_string(&media).unwrap();
+
+    for key in keys {
+        remember(key, media.clone()).await;
+    }
+
+    let mut connection = match database::connection().await {
+        Ok(connection) => connection,
+        Err(_) => return,
+    };
+
+    for key in keys {
+        let _: RedisResult<()> = connection
+            .set_ex(redis_key(key), value.clone(), CACHE_TTL.as_secs() as usize)
+            .await;
+    }
+}
+
+pub async fn stats() -> CacheStats {
+    let memory_entries = ENTRIES.lock().await.len();
+    let redis_entries = redis_keys().await.map(|keys| keys.len()).unwrap_or(0);
+
+    CacheStats {
+        hits: HITS.load(Ordering::Relaxed),
+        misses: MISSES.load(Ordering::Relaxed),
+        memory_entries,
+        redis_entries,
+    }
+}
+
+pub async fn clear() -> RedisResult<usize> {
+    ENTRIES.lock().await.clear();
+
+    HITS.store(0, Ordering::Relaxed);
+    MISSES.store(0, Ordering::Relaxed);
+
+    let keys = redis_keys().await?;
+
+    if keys.is_empty() {
+        return Ok(0);
+    }
+
+    let mut connection = database::connection().await?;
+
+    let _: () = connection.del(&keys).await?;
+
+    Ok(keys.len())
+}
+
+async fn remember(key: &str, media: CachedMedia) {
+    let mut entries = ENTRIES.lock().await;
+
+    if entries.len() >= MEMORY_LIMIT {
+        entries.retain(|_, (stored_at, _)| stored_at.elapsed() < CACHE_TTL);
+    }
+
+    if entries.len() >= MEMORY_LIMIT {
+        let oldest = entries
+            .iter()
+            .min_by_key(|(_, (stored_at, _))| *stored_at)
+            .map(|(key, _)| key.clone());
+
+        if let Some(oldest) = oldest {
+            entries.remove(&oldest);
+        }
+    }
+
+    entries.insert(key.to_string(), (Instant::now(), media));
+}
+
+async fn load(key: &str) -> Option<CachedMedia> {
+    let mut connection = database::connection().await.ok()?;
+
+    let value: Option<String> = connection.get(redis_key(key)).await.ok()?;
+
+    serde_json::from_str(&value?).ok()
+}
+
+async fn redis_keys() -> RedisResult<Vec<String>> {
+    let mut connection = database::connection().await?;
+    let mut iter = connection.scan_match::<_, String>(redis_key("*")).await?;
+
+    let mut keys = vec![];
+
+    while let Some(key) = iter.next_item().await {
+        keys.push(key);
+    }
+
+    Ok(keys)
+}
+
+fn redis_key(key: &str) -> String {
+    database::key(&format!("media:{key}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize_url_keeps_only_the_youtube_video() {
+        let cases = [
+            (
+                "https://youtu.be/abc123?si=xyz",
+                "https://youtube.com/watch?v=abc123",
+            ),
+            (
+                "http://www.youtube.com/watch?v=abc&list=PL1&t=10",
+                "https://youtube.com/watch?v=abc",
+            ),
+            (
+                "https://m.youtube.com/watch?v=abc",
+                "https://youtube.com/watch?v=abc",
+            ),
+            (
+                "https://music.youtube.com/watch?v=abc&feature=share",
+                "https://music.youtube.com/watch?v=abc",
+            ),
+        ];
+
+        for (url, normalized) in cases {
+            assert_eq!(normalize_url(url), normalized, "{url}");
+        }
+    }
+
+    #[test]
+    fn normalize_url_drops_tracking_parameters() {
+        assert_eq!(
+            normalize_url("https://soundcloud.com/artist/track?utm_source=x&in=set#t=10"),
+            "https://soundcloud.com/artist/track?in=set"
+        );
+        assert_eq!(
+            normalize_url("https://www.example.com/"),
+            "https://example.com"
+        );
+    }
+
+    #[test]
+    fn normalize_url_keeps_invalid_urls() {
+        assert_eq!(normalize_url(" not a url "), "not a url");
+    }
+
+    #[test]
+    fn metadata_keeps_channel_and_date() {
+        let metadata = Metadata {
+            title: Some("Song".to_string()),
+            channel: Some("Channel".to_string()),
+            date: Some("20240131".to_string()),
+            duration: Some(Duration::from_secs(90)),
+            source_url: Some("https://youtube.com/watch?v=a".to_string()),
+            ..Metadata::default()
+        };
+
+        let media = CachedMedia::from_metadata(&metadata, SearchSource::YouTube, "https://a")
+            .unwrap()
+            .metadata();
+
+        assert_eq!(media.channel.as_deref(), Some("Channel"));
+        assert_eq!(media.date.as_deref(), Some("20240131"));
+    }
+
+    #[test]
+    fn old_entries_load_without_channel_and_date() {
+        let media: CachedMedia = serde_json::from_str(
+            r#"{"title":"Song","artist":"Artist","duration":90,"thumbnail":null,"url":"https://a","origin":"Bandcamp"}"#,
+        )
+        .unwrap();
+        let metadata = media.metadata();
+
+        assert_eq!(metadata.channel.as_deref(), Some("Artist"));
+        assert_eq!(metadata.date, None);
+    }
+
+    #[test]
+    fn search_key_ignores_case_and_spacing() {
+        assert_eq!(
+            search_key(SearchSource::YouTube, "  Never   Gonna Give "),
+            search_key(SearchSource::YouTube, "never gonna give")
+        );
+        assert_ne!(
+            search_key(SearchSource::YouTube, "song"),
+            search_key(SearchSource::SoundCloud, "song")
+        );
+    }
+}
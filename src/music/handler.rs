@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Instant;
 
@@ -11,6 +12,7 @@ use songbird::{EventHandler, Songbird};
 
 use super::player::{self, next};
 use super::playlist;
+use super::speech;
 
 use super::send_media_message;
 
@@ -21,8 +23,16 @@ pub struct StopMusicHandle {
 }
 
 #[async_trait]
-impl<'fut> EventHandler for StopMusicHandle {
-    async fn act(&self, _ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+impl EventHandler for StopMusicHandle {
+    async fn act(&self, ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        if let songbird::EventContext::Track(tracks) = ctx {
+            for (_, track_handle) in tracks.iter() {
+                if player::is_superseded(track_handle).await {
+                    return None;
+                }
+            }
+        }
+
         let playing_next = next(&self.ctx, self.guild_id.0, self.channel_id.0).await;
 
         if playing_next {
@@ -41,4 +51,46 @@ impl<'fut> EventHandler for StopMusicHandle {
     }
 }
 
+pub struct PrepareNextHandle {
+    pub ctx: Context,
+    pub guild_id: GuildId,
+}
+
+#[async_trait]
+impl EventHandler for PrepareNextHandle {
+    async fn act(&self, _ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        player::prepare_next(&self.ctx, self.guild_id.0).await;
+
+        None
+    }
+}
+
+pub struct CrossfadeHandle {
+    pub ctx: Context,
+    pub guild_id: GuildId,
+}
+
+#[async_trait]
+impl EventHandler for CrossfadeHandle {
+    async fn act(&self, _ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        player::crossfade(&self.ctx, self.guild_id.0).await;
+
+        None
+    }
+}
+
+pub struct SpeechEndHandle {
+    pub guild_id: u64,
+    pub path: PathBuf,
+}
+
+#[async_trait]
+impl EventHandler for SpeechEndHandle {
+    async fn act(&self, _ctx: &songbird::EventContext<'_>) -> Option<songbird::Event> {
+        speech::finish(self.guild_id, &self.path).await;
+
+        None
+    }
+}
+
 pub async fn handle_next() {}
@@ -0,0 +1,362 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use serenity::{
+    builder::{CreateComponents, CreateEmbed},
+    model::prelude::{
+        component::ButtonStyle,
+        interaction::{message_component::MessageComponentInteraction, InteractionResponseType},
+        ChannelId, Message, MessageId,
+    },
+    prelude::Context,
+};
+use tokio::sync::Mutex;
+
+use super::player::{self, format_duration};
+use super::playlist;
+use crate::settings;
+
+const MENU_TIMEOUT: Duration = Duration::from_secs(120);
+const NOW_PLAYING_PAGE: usize = 0;
+const MAX_FIELD_LENGTH: usize = 1024;
+// Espaço guardado para o aviso de músicas que não couberam no campo.
+const OVERFLOW_RESERVE: usize = 40;
+
+#[derive(Clone)]
+struct PlaylistMenu {
+    guild_id: u64,
+    page: usize,
+    author_name: String,
+    author_icon: String,
+    last_used: Instant,
+}
+
+lazy_static! {
+    static ref MENUS: Mutex<HashMap<u64, PlaylistMenu>> = Mutex::new(HashMap::new());
+}
+
+pub async fn send(ctx: &Context, msg: &Message, page: usize) {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let mut menu = PlaylistMenu {
+        guild_id,
+        page,
+        author_name: msg.author.name.clone(),
+        author_icon: msg.author.face(),
+        last_used: Instant::now(),
+    };
+
+    let (embed, components) = match render(&mut menu).await {
+        Some(rendered) => rendered,
+        None => {
+            let _ = msg
+                .reply(&ctx.http, "Não há nenhuma música na playlist.")
+                .await;
+
+            return;
+        }
+    };
+
+    let sent = msg
+        .channel_id
+        .send_message(&ctx.http, |m| m.set_embed(embed).set_components(components))
+        .await;
+
+    let message = match sent {
+        Ok(message) => message,
+        Err(_) => {
+            let _ = msg
+                .reply(&ctx.http, "Não foi possível mostrar a playlist.")
+                .await;
+
+            return;
+        }
+    };
+
+    MENUS.lock().await.insert(message.id.0, menu);
+
+    expire(ctx.clone(), message.channel_id, message.id);
+}
+
+pub async fn handle_component(ctx: &Context, component: &MessageComponentInteraction) {
+    let action = match component.data.custom_id.strip_prefix("playlist:") {
+        Some(action) => action.to_string(),
+        None => return,
+    };
+
+    let menu = MENUS.lock().await.get(&component.message.id.0).cloned();
+
+    let mut menu = match menu {
+        Some(menu) => menu,
+        None => {
+            let _ = component
+                .create_interaction_response(&ctx.http, |r| {
+                    r.kind(InteractionResponseType::ChannelMessageWithSource)
+                        .interaction_response_data(|d| {
+                            d.content("Este menu expirou, use `!playlist` novamente.")
+                                .ephemeral(true)
+                        })
+                })
+                .await;
+
+            return;
+        }
+    };
+
+    let total_pages = total_pages(menu.guild_id).await;
+
+    menu.last_used = Instant::now();
+    menu.page = match action.as_str() {
+        "first" => 1,
+        "previous" => menu.page.saturating_sub(1).max(1),
+        "next" => menu.page + 1,
+        "last" => total_pages,
+        "now" => NOW_PLAYING_PAGE,
+        _ => menu.page,
+    };
+
+    // O render acessa o Redis e o player, então roda sem segurar o mapa de
+    // menus de todos os servidores.
+    let rendered = render(&mut menu).await;
+
+    if let Some(stored) = MENUS.lock().await.get_mut(&component.message.id.0) {
+        stored.page = menu.page;
+        stored.last_used = menu.last_used;
+    }
+
+    let (embed, components) = match rendered {
+        Some(rendered) => rendered,
+        None => (empty_embed(), CreateComponents::default()),
+    };
+
+    let _ = component
+        .create_interaction_response(&ctx.http, |r| {
+            r.kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|d| d.set_embed(embed).set_components(components))
+        })
+        .await;
+}
+
+fn expire(ctx: Context, channel_id: ChannelId, message_id: MessageId) {
+    tokio::spawn(async move {
+        let mut wait = MENU_TIMEOUT;
+
+        loop {
+            tokio::time::sleep(wait).await;
+
+            let mut menus = MENUS.lock().await;
+
+            let elapsed = match menus.get(&message_id.0) {
+                Some(menu) => menu.last_used.elapsed(),
+                None => return,
+            };
+
+            if elapsed < MENU_TIMEOUT {
+                wait = MENU_TIMEOUT - elapsed;
+                continue;
+            }
+
+            menus.remove(&message_id.0);
+
+            break;
+        }
+
+        let _ = channel_id
+            .edit_message(&ctx.http, message_id, |m| {
+                m.set_components(CreateComponents::default())
+            })
+            .await;
+    });
+}
+
+async fn total_pages(guild_id: u64) -> usize {
+    let page_size = settings::get(guild_id).await.page_size();
+    let len = playlist::len(guild_id).await;
+
+    len.div_ceil(page_size).max(1)
+}
+
+async fn render(menu: &mut PlaylistMenu) -> Option<(CreateEmbed, CreateComponents)> {
+    let page_size = settings::get(menu.guild_id).await.page_size();
+    let total_pages = total_pages(menu.guild_id).await;
+    let now_playing = player::now_playing_info(menu.guild_id).await;
+
+    if now_playing.is_none() && playlist::len(menu.guild_id).await == 0 {
+        return None;
+    }
+
+    menu.page = menu.page.min(total_pages);
+
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .author(|a| {
+            a.name(menu.author_name.clone())
+                .icon_url(menu.author_icon.clone())
+        })
+        .description("Playlist");
+
+    if menu.page == NOW_PLAYING_PAGE {
+        match &now_playing {
+            Some(now_playing) => {
+                let media_info = &now_playing.media_info;
+
+                embed
+                    .thumbnail(media_info.thumb.clone())
+                    .field("Tocando agora", media_info.title.clone(), false)
+                    .field("Canal", media_info.artist.clone(), true)
+                    .field("Fonte", media_info.origin.name(), true)
+                    .field(
+                        "Duração",
+                        format!(
+                            "{}/{}",
+                            format_duration(Some(now_playing.position)),
+                            media_info.duration
+                        ),
+                        true,
+                    )
+                    .field(
+                        "Pedido por",
+                        now_playing.requester.clone().unwrap_or("???".to_string()),
+                        true,
+                    );
+            }
+            None => {
+                embed.field("Tocando agora", "Nada", false);
+            }
+        }
+    } else {
+        let playlist_info = playlist::info(menu.guild_id, menu.page, page_size).await;
+
+        let mut items_str = String::new();
+        let mut total_tracks = 0;
+        let mut total_duration = "00:00".to_string();
+
+        if let Some(playlist_info) = playlist_info {
+            total_tracks = playlist_info.total_tracks;
+            total_duration = match playlist_info.remaining_duration {
+                Some(remaining) => format!("~{}", format_duration(Some(remaining))),
+                None => format!(
+                    "{} + ao vivo",
+                    format_duration(Some(playlist_info.total_duration))
+                ),
+            };
+
+            // O campo do embed tem no máximo 1024 caracteres, então títulos e
+            // artistas encurtam conforme o tamanho da página.
+            let budget = (MAX_FIELD_LENGTH - OVERFLOW_RESERVE) / playlist_info.items.len().max(1);
+
+            for (i, item_info) in playlist_info.items.iter().enumerate() {
+                let index = item_info.index + 1;
+                let origin = item_info.media_info.origin.name();
+                let requester = truncate(&item_info.requester, 32);
+                let starts_in = match item_info.starts_in {
+                    Some(starts_in) => format!("~{}", format_duration(Some(starts_in))),
+                    None => "?".to_string(),
+                };
+
+                let fixed = format!(
+                    "`{index}° -  | ({origin})`\nPedido por {requester}, toca em {starts_in}\n"
+                )
+                .chars()
+                .count();
+                let room = budget.saturating_sub(fixed);
+
+                let title = truncate(&item_info.media_info.title, (room * 3 / 5).clamp(10, 50));
+                let artist = truncate(&item_info.media_info.artist, (room * 2 / 5).clamp(10, 30));
+
+                let line = format!(
+                    "`{index}° - {title} | {artist} ({origin})`\nPedido por {requester}, toca em {starts_in}"
+                );
+
+                let length = items_str.chars().count() + line.chars().count() + 1;
+
+                if length > MAX_FIELD_LENGTH - OVERFLOW_RESERVE {
+                    let hidden = playlist_info.items.len() - i;
+
+                    items_str.push_str(&format!("\n… e mais {hidden} nesta página"));
+
+                    break;
+                }
+
+                if i > 0 {
+                    items_str.push('\n');
+                }
+
+                items_str.push_str(&line);
+            }
+        }
+
+        if items_str.is_empty() {
+            items_str.push_str("Nenhuma música na fila.");
+        }
+
+        embed
+            .field("Musgas", total_tracks, true)
+            .field("Paginas", total_pages, true)
+            .field("Duração restante", total_duration, true)
+            .field("Musga de homi", items_str, false);
+    }
+
+    embed.footer(|f| f.text("Bora beber pinga"));
+
+    let page = menu.page;
+    let mut components = CreateComponents::default();
+
+    components.create_action_row(|row| {
+        row.create_button(|b| {
+            b.custom_id("playlist:first")
+                .label("⏮")
+                .style(ButtonStyle::Secondary)
+                .disabled(page == 1)
+        })
+        .create_button(|b| {
+            b.custom_id("playlist:previous")
+                .label("◀")
+                .style(ButtonStyle::Secondary)
+                .disabled(page <= 1)
+        })
+        .create_button(|b| {
+            b.custom_id("playlist:now")
+                .label("Tocando agora")
+                .style(ButtonStyle::Primary)
+                .disabled(page == NOW_PLAYING_PAGE)
+        })
+        .create_button(|b| {
+            b.custom_id("playlist:next")
+                .label("▶")
+                .style(ButtonStyle::Secondary)
+                .disabled(page >= total_pages)
+        })
+        .create_button(|b| {
+            b.custom_id("playlist:last")
+                .label("⏭")
+                .style(ButtonStyle::Secondary)
+                .disabled(page >= total_pages)
+        })
+    });
+
+    Some((embed, components))
+}
+
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+
+    format!("{}…", text.chars().take(max - 1).collect::<String>())
+}
+
+fn empty_embed() -> CreateEmbed {
+    let mut embed = CreateEmbed::default();
+
+    embed
+        .description("Playlist")
+        .field("Musga de homi", "Não há nenhuma música na playlist.", false)
+        .footer(|f| f.text("Bora beber pinga"));
+
+    embed
+}
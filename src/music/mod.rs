@@ -1,3 +1,4 @@
+use std::borrow::Cow;
 use std::time::Duration;
 use std::{sync::Arc, thread};
 
@@ -17,27 +18,47 @@ use serenity::{
     model::{
         channel::Message,
         id::GuildId,
-        prelude::{Channel, ChannelId, Member},
+        prelude::{AttachmentType, Channel, ChannelId, ChannelType, GuildChannel, Member},
         user::User,
     },
-    utils::{Color, MessageBuilder},
+    utils::{content_safe, ArgumentConvert, Color, ContentSafeOptions, MessageBuilder},
     FutureExt,
 };
 use songbird::input::Input;
 use songbird::tracks::TrackHandle;
 use songbird::{EventHandler, Songbird, TrackEvent};
 
+pub mod cache;
 mod handler;
+pub mod menu;
 pub mod player;
 pub mod playlist;
 pub mod query;
+pub mod saved;
+pub mod soundboard;
+pub mod speech;
+pub mod stage;
+pub mod transfer;
 
 use player::{PlayerError, PlayerStatus};
 
-use self::player::{format_duration, MediaInfo};
+use self::player::{format_date, format_duration, MediaInfo};
+use self::playlist::Placement;
+use self::query::SearchSource;
+use self::saved::{SavedError, SavedScope, SavedTrack};
+use self::soundboard::SoundboardError;
+use self::speech::SpeechError;
+use self::stage::SpeakerStatus;
+use self::transfer::QueueFormat;
+use crate::moderation::{self, LogEntry};
+use crate::settings;
 
 #[group]
-#[commands(play, pause, unpause, trackinfo, volume, skip, stop, playlist)]
+#[commands(
+    play, playnext, playtop, playskip, pause, unpause, trackinfo, volume, skip, stop, playlist,
+    source, cache, saved, queue, limits, dj, crossfade, sfx, say, announce, join, leave, move_to,
+    stay, stagetopic, fairqueue
+)]
 struct Music;
 
 pub async fn send_media_message(
@@ -51,19 +72,19 @@ pub async fn send_media_message(
     let thumb = metadata.thumbnail.clone().unwrap_or("???".to_string());
     let channel = metadata.channel.clone().unwrap_or("???".to_string());
     let title = metadata.title.clone().unwrap_or("???".to_string());
-    let duration = format_duration(metadata.duration.clone());
+    let duration = format_duration(metadata.duration);
     let url = metadata.clone().source_url.unwrap_or("???".to_string());
+    let origin = track_handle
+        .typemap()
+        .read()
+        .await
+        .get::<SearchSource>()
+        .copied()
+        .unwrap_or_default();
 
-    let raw_date = metadata.clone().date.unwrap();
+    let date = format_date(metadata.date.as_deref());
 
-    let date = unsafe {
-        format!(
-            "{}/{}/{}",
-            raw_date.get_unchecked(6..8),
-            raw_date.get_unchecked(4..6),
-            raw_date.get_unchecked(0..4),
-        )
-    };
+    stage::update_topic(ctx, member.guild_id, &title).await;
 
     let mut msg = CreateMessage::default();
 
@@ -72,7 +93,7 @@ pub async fn send_media_message(
             .color(0xc3e2e1)
             .author(|a| {
                 a.name(member.user.name.clone())
-                    .icon_url(member.user.avatar_url().unwrap())
+                    .icon_url(member.user.face())
             })
             .thumbnail(
                 "https://cdn.icon-icons.com/icons2/1429/PNG/512/icon-robots-16_98547.png"
@@ -86,6 +107,7 @@ pub async fn send_media_message(
             .field("", "", false)
             .field("Duração", duration, true)
             .field("Data", date, true)
+            .field("Fonte", origin.name(), true)
             .field("", "", false)
             .field("URL", url, false)
     });
@@ -97,29 +119,153 @@ pub async fn send_media_message(
         .await;
 }
 
+pub struct EnqueueReport {
+    pub added: usize,
+    pub failed: usize,
+    pub rejected: usize,
+    pub skipped: usize,
+    pub stopped_by: Option<PlayerError>,
+}
+
+impl EnqueueReport {
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "{} adicionadas, {} não encontradas.",
+            self.added, self.failed
+        );
+
+        if self.rejected > 0 {
+            summary.push_str(&format!(
+                "\n{} músicas recusadas pelos limites do servidor.",
+                self.rejected
+            ));
+        }
+
+        if let Some(why) = self.stopped_by {
+            summary.push_str(&format!(
+                "\n{why} {} músicas foram ignoradas.",
+                self.skipped
+            ));
+        }
+
+        summary
+    }
+}
+
+pub async fn enqueue_all(
+    ctx: &Context,
+    member: &Member,
+    channel_id: ChannelId,
+    mut uris: Vec<String>,
+) -> EnqueueReport {
+    let mut report = EnqueueReport {
+        added: 0,
+        failed: 0,
+        rejected: 0,
+        skipped: 0,
+        stopped_by: None,
+    };
+
+    // Só resolve o que ainda cabe na playlist.
+    let capacity = settings::get(member.guild_id.0)
+        .await
+        .playlist_limit()
+        .saturating_sub(playlist::len(member.guild_id.0).await);
+
+    if uris.len() > capacity {
+        report.skipped = uris.len() - capacity;
+        report.stopped_by = Some(PlayerError::PlaylistFull);
+        uris.truncate(capacity);
+    }
+
+    let total = uris.len();
+
+    for (i, uri) in uris.into_iter().enumerate() {
+        let status = player::add(
+            ctx.clone(),
+            uri,
+            member.guild_id,
+            channel_id,
+            member.clone(),
+            Placement::Back,
+        )
+        .await;
+
+        match status {
+            Ok(PlayerStatus::Playing(track_handle)) => {
+                send_media_message(ctx, member, channel_id, track_handle).await;
+                report.added += 1;
+            }
+            Ok(PlayerStatus::Queued(_)) | Ok(PlayerStatus::Skipped) => report.added += 1,
+            Err(PlayerError::MusicNotFound) => report.failed += 1,
+            Err(PlayerError::LiveNotAllowed)
+            | Err(PlayerError::TrackTooLong(_))
+            | Err(PlayerError::QueueTooLong(_)) => report.rejected += 1,
+            Err(why) => {
+                report.stopped_by = Some(why);
+                report.skipped += total - i;
+                break;
+            }
+        }
+    }
+
+    report
+}
+
 #[command]
 #[only_in(guilds)]
 pub async fn play(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    enqueue(ctx, msg, args.message(), Placement::Back).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn playnext(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    enqueue(ctx, msg, args.message(), Placement::Front).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn playtop(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    enqueue(ctx, msg, args.message(), Placement::Top).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn playskip(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    enqueue(ctx, msg, args.message(), Placement::Skip).await
+}
+
+async fn enqueue(ctx: &Context, msg: &Message, uri: &str, placement: Placement) -> CommandResult {
     let member = &msg.member(&ctx.http).await.unwrap();
 
     let status = player::add(
         ctx.clone(),
-        args.message().to_string(),
+        uri.to_string(),
         msg.guild_id.unwrap(),
         msg.channel_id,
         member.clone(),
+        placement,
     )
     .await;
 
     match status {
-        Ok(PlayerStatus::Queued) => {
-            msg.reply(&ctx.http, "A sua música foi adicionada na playlist.")
+        Ok(PlayerStatus::Queued(queue_position)) => {
+            msg.reply(
+                &ctx.http,
+                format!("A sua música foi adicionada na playlist, {queue_position}."),
+            )
+            .await
+            .unwrap();
+        }
+        Ok(PlayerStatus::Skipped) => {
+            msg.reply(&ctx.http, "Música atual pulada, a sua já vai tocar.")
                 .await
                 .unwrap();
         }
         Ok(PlayerStatus::Playing(media_info)) => {
             send_media_message(
-                &ctx,
+                ctx,
                 &msg.member(&ctx.http).await.unwrap(),
                 msg.channel_id,
                 media_info.to_owned(),
@@ -136,10 +282,12 @@ pub async fn play(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
                 .await
                 .unwrap();
         }
-        _ => {}
+        Err(why) => {
+            msg.reply(&ctx.http, why.to_string()).await.unwrap();
+        }
     }
 
-    return Ok(());
+    Ok(())
 }
 
 #[command]
@@ -147,7 +295,7 @@ pub async fn play(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult
 pub async fn pause(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     player::pause(ctx, msg.guild_id.unwrap()).await;
 
-    return Ok(());
+    Ok(())
 }
 
 #[command]
@@ -155,7 +303,7 @@ pub async fn pause(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 pub async fn unpause(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     player::unpause(ctx, msg.guild_id.unwrap()).await;
 
-    return Ok(());
+    Ok(())
 }
 
 #[command]
@@ -165,7 +313,7 @@ pub async fn trackinfo(ctx: &Context, msg: &Message, args: Args) -> CommandResul
 
     msg.reply(&ctx.http, info).await.unwrap();
 
-    return Ok(());
+    Ok(())
 }
 
 #[command]
@@ -177,68 +325,494 @@ pub async fn volume(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
 
     msg.reply(&ctx.http, info).await.unwrap();
 
-    return Ok(());
+    Ok(())
 }
 
 #[command]
 #[only_in(guilds)]
 pub async fn playlist(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
-    let page = args.parse::<usize>().unwrap_or(1);
-    let guild_id = msg.guild(&ctx.cache).unwrap().id.0;
-    let channel_id = msg.channel_id.0;
+    let page = args.parse::<usize>().unwrap_or(1).max(1);
+
+    menu::send(ctx, msg, page).await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn skip(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let playing_next = player::skip(ctx, msg.guild_id.unwrap().0, msg.channel_id.0).await;
+
+    if !playing_next {
+        msg.reply(&ctx.http, "Não há mais nenhuma música na playlist.")
+            .await
+            .unwrap();
+    }
+
+    Ok(())
+}
 
-    let playlist_info = playlist::info(guild_id, page, 3).await;
+#[command]
+#[only_in(guilds)]
+pub async fn stop(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    player::stop(ctx, guild_id).await;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn source(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    if args.is_empty() {
+        let current = settings::get(guild_id).await.search_source;
+
+        msg.reply(
+            &ctx.http,
+            format!(
+                "Fonte de busca padrão: {}.\nFontes disponíveis: `yt`, `ytm`, `sc` e `bc`.",
+                current.name()
+            ),
+        )
+        .await
+        .unwrap();
 
-    if playlist_info.is_none() {
         return Ok(());
     }
 
-    let playlist_info = playlist_info.unwrap();
+    let search_source = match SearchSource::from_prefix(args.rest().trim()) {
+        Some(search_source) => search_source,
+        None => {
+            msg.reply(
+                &ctx.http,
+                "Fonte desconhecida, use `yt`, `ytm`, `sc` ou `bc`.",
+            )
+            .await
+            .unwrap();
 
-    let mut items_str = String::new();
+            return Ok(());
+        }
+    };
+
+    let result = settings::update(guild_id, |settings| {
+        settings.search_source = search_source;
+    })
+    .await;
+
+    let content = match result {
+        Ok(_) => format!(
+            "Fonte de busca padrão alterada para {}.",
+            search_source.name()
+        ),
+        Err(_) => "Não foi possível salvar a configuração.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await.unwrap();
+
+    Ok(())
+}
+
+#[command]
+#[owners_only]
+pub async fn cache(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let action = args.single::<String>().unwrap_or_default();
 
-    for (i, item_info) in playlist_info.items.iter().enumerate() {
-        if i > 0 {
-            items_str.push_str("\n");
+    let content = match action.as_str() {
+        "stats" => {
+            let stats = cache::stats().await;
+            let total = stats.hits + stats.misses;
+            let hit_rate = (stats.hits * 100).checked_div(total).unwrap_or(0);
+
+            format!(
+                "Entradas em memória: {}\nEntradas no Redis: {}\nAcertos: {} ({}%)\nFalhas: {}",
+                stats.memory_entries, stats.redis_entries, stats.hits, hit_rate, stats.misses
+            )
+        }
+        "clear" => match cache::clear().await {
+            Ok(removed) => format!("Cache limpo, {removed} entradas removidas do Redis."),
+            Err(_) => "Cache em memória limpo, mas não foi possível limpar o Redis.".to_string(),
+        },
+        _ => "Use `!cache stats` ou `!cache clear`.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await.unwrap();
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn saved(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let user_id = msg.author.id.0;
+    let action = args.single::<String>().unwrap_or_default();
+
+    let member = msg.member(&ctx.http).await?;
+    let is_manager = member
+        .permissions(&ctx.cache)
+        .map(|permissions| permissions.manage_guild())
+        .unwrap_or(false);
+
+    let content = match action.as_str() {
+        "save" => {
+            let name = args.rest();
+            let mut tracks: Vec<SavedTrack> = vec![];
+
+            if let Some(media_info) = player::now_playing(guild_id.0).await {
+                tracks.push(SavedTrack::from(&media_info));
+            }
+
+            for media_info in playlist::entries(guild_id.0).await.iter() {
+                tracks.push(SavedTrack::from(media_info));
+            }
+
+            let total = tracks.len();
+
+            match saved::save(user_id, name, tracks).await {
+                Ok(_) => format!("Playlist `{}` salva com {total} músicas.", name.trim()),
+                Err(why) => saved_error_message(why),
+            }
         }
+        "load" => {
+            let name = args.rest();
 
-        let index = item_info.index + 1;
-        let title = item_info.media_info.title.clone();
-        let artist = item_info.media_info.artist.clone();
+            match saved::find(guild_id.0, user_id, name).await {
+                Ok((_, saved_playlist)) => {
+                    let urls = saved_playlist
+                        .tracks
+                        .into_iter()
+                        .map(|track| track.url)
+                        .collect();
+
+                    let report = enqueue_all(ctx, &member, msg.channel_id, urls).await;
+
+                    format!(
+                        "Playlist `{}` carregada: {}",
+                        saved_playlist.name,
+                        report.summary()
+                    )
+                }
+                Err(why) => saved_error_message(why),
+            }
+        }
+        "list" => match saved::list(guild_id.0, user_id).await {
+            Ok((own, shared)) => {
+                let format_list = |playlists: Vec<saved::SavedPlaylist>| {
+                    if playlists.is_empty() {
+                        return "Nenhuma".to_string();
+                    }
+
+                    playlists
+                        .iter()
+                        .map(|p| format!("`{}` ({} músicas)", p.name, p.tracks.len()))
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                };
+
+                format!(
+                    "**Suas playlists ({}/{}):**\n{}\n**Playlists do servidor:**\n{}",
+                    own.len(),
+                    saved::MAX_USER_PLAYLISTS,
+                    format_list(own),
+                    format_list(shared)
+                )
+            }
+            Err(why) => saved_error_message(why),
+        },
+        "delete" => {
+            let name = args.rest();
+
+            match saved::delete(guild_id.0, user_id, name, is_manager).await {
+                Ok(_) => format!("Playlist `{}` apagada.", name.trim()),
+                Err(why) => saved_error_message(why),
+            }
+        }
+        "rename" => {
+            let name = args.single_quoted::<String>().unwrap_or_default();
+            let new_name = args.rest();
+
+            match saved::rename(guild_id.0, user_id, &name, new_name, is_manager).await {
+                Ok(_) => format!("Playlist `{name}` renomeada para `{}`.", new_name.trim()),
+                Err(why) => saved_error_message(why),
+            }
+        }
+        "share" => {
+            let name = args.rest();
 
-        items_str.push_str(format!("`{index}° - {title} | {artist}`").as_str());
+            match saved::share(guild_id.0, user_id, name).await {
+                Ok(_) => format!("Playlist `{}` compartilhada com o servidor.", name.trim()),
+                Err(why) => saved_error_message(why),
+            }
+        }
+        _ => "Use `!saved save|load|list|delete|rename|share <nome>`.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+fn saved_error_message(error: SavedError) -> String {
+    match error {
+        SavedError::InvalidName => {
+            "O nome da playlist deve ter entre 1 e 32 caracteres.".to_string()
+        }
+        SavedError::Empty => "Não há nenhuma música para salvar.".to_string(),
+        SavedError::TooManyTracks => format!(
+            "Uma playlist pode ter no máximo {} músicas.",
+            saved::MAX_PLAYLIST_TRACKS
+        ),
+        SavedError::LimitReached => "O limite de playlists salvas foi atingido.".to_string(),
+        SavedError::AlreadyExists => "Já existe uma playlist com esse nome.".to_string(),
+        SavedError::NotFound => "Playlist não encontrada.".to_string(),
+        SavedError::NotOwner => "Somente o dono da playlist pode alterá-la.".to_string(),
+        SavedError::Database => "Não foi possível acessar as playlists salvas.".to_string(),
     }
+}
 
-    let mut msg_build = CreateMessage::default();
+#[command]
+#[only_in(guilds)]
+pub async fn queue(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let action = args.single::<String>().unwrap_or_default();
 
-    msg_build.embed(|e| {
-        e.author(|a| {
-            a.name(msg.author.name.clone())
-                .icon_url(msg.author.avatar_url().unwrap())
-        })
-        .description("Playlist")
-        .field("Musgas", playlist_info.total_tracks, true)
-        .field("Paginas", playlist_info.total_pages, true)
-        .field("Musga de homi", items_str, false)
-        .footer(|f| f.text("Bora beber pinga"))
-    });
+    match action.as_str() {
+        "export" => {
+            let format = match QueueFormat::from_name(args.rest()) {
+                Some(format) => format,
+                None => {
+                    msg.reply(&ctx.http, "Formato inválido, use `json` ou `m3u`.")
+                        .await?;
+
+                    return Ok(());
+                }
+            };
+
+            let mut tracks = vec![];
+
+            if let Some(media_info) = player::now_playing(guild_id).await {
+                tracks.push(media_info);
+            }
+
+            tracks.extend(playlist::entries(guild_id).await);
+
+            if tracks.is_empty() {
+                msg.reply(&ctx.http, "Não há nenhuma música na playlist.")
+                    .await?;
+
+                return Ok(());
+            }
+
+            let content = transfer::export(&tracks, format);
+            let filename = format!("playlist-{guild_id}.{}", format.extension());
+
+            msg.channel_id
+                .send_message(&ctx.http, |m| {
+                    m.content(format!("Playlist exportada com {} músicas.", tracks.len()))
+                        .add_file(AttachmentType::Bytes {
+                            data: Cow::from(content.into_bytes()),
+                            filename,
+                        })
+                })
+                .await?;
+        }
+        "import" => {
+            let attachment = match msg.attachments.first() {
+                Some(attachment) => attachment,
+                None => {
+                    msg.reply(
+                        &ctx.http,
+                        "Anexe um arquivo JSON, M3U ou uma lista de URLs.",
+                    )
+                    .await?;
+
+                    return Ok(());
+                }
+            };
+
+            if attachment.size > transfer::MAX_IMPORT_SIZE {
+                msg.reply(&ctx.http, "O arquivo é grande demais.").await?;
+
+                return Ok(());
+            }
 
-    let map = hashmap_to_json_map(msg_build.0);
+            let content = match attachment.download().await {
+                Ok(bytes) => String::from_utf8_lossy(&bytes).to_string(),
+                Err(_) => {
+                    msg.reply(&ctx.http, "Não foi possível baixar o arquivo.")
+                        .await?;
 
-    ctx.http.send_message(channel_id, &Value::Object(map)).await;
+                    return Ok(());
+                }
+            };
 
-    return Ok(());
+            let uris = transfer::parse(&content);
+
+            if uris.is_empty() {
+                msg.reply(&ctx.http, "Nenhuma música encontrada no arquivo.")
+                    .await?;
+
+                return Ok(());
+            }
+
+            let member = msg.member(&ctx.http).await?;
+            let report = enqueue_all(ctx, &member, msg.channel_id, uris).await;
+
+            msg.reply(
+                &ctx.http,
+                format!("Importação concluída: {}", report.summary()),
+            )
+            .await?;
+        }
+        _ => {
+            msg.reply(
+                &ctx.http,
+                "Use `!queue export [json|m3u]` ou `!queue import` com um arquivo anexado.",
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
 }
 
 #[command]
 #[only_in(guilds)]
-pub async fn skip(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
-    let playing_next = player::skip(&ctx, msg.guild_id.unwrap().0, msg.channel_id.0).await;
+pub async fn limits(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let limit = args.single::<String>().unwrap_or_default();
+    let value = args.rest().trim().to_lowercase();
+    let disable = value == "off" || value == "0";
 
-    if !playing_next {
-        msg.reply(&ctx.http, "Não há mais nenhuma música na playlist.")
-            .await
-            .unwrap();
+    let seconds = humantime::parse_duration(&value)
+        .ok()
+        .map(|duration| duration.as_secs())
+        .filter(|seconds| *seconds > 0);
+    let count = value.parse::<usize>().ok().filter(|count| *count > 0);
+
+    let updated = match limit.as_str() {
+        "" => None,
+        "user" if disable || count.is_some() => {
+            Some(settings::update(guild_id, |s| s.max_entries_per_user = count).await)
+        }
+        "track" if disable || seconds.is_some() => {
+            Some(settings::update(guild_id, |s| s.max_track_duration = seconds).await)
+        }
+        "queue" if disable || seconds.is_some() => {
+            Some(settings::update(guild_id, |s| s.max_queue_duration = seconds).await)
+        }
+        "live" if value == "on" || value == "off" => {
+            Some(settings::update(guild_id, |s| s.block_live = value == "off").await)
+        }
+        "size" if count.is_some_and(|count| count <= playlist::MAX_PLAYLIST_LIMIT) => {
+            Some(settings::update(guild_id, |s| s.playlist_limit = count).await)
+        }
+        "page" if count.is_some_and(|count| count <= playlist::MAX_PAGE_SIZE) => {
+            Some(settings::update(guild_id, |s| s.page_size = count).await)
+        }
+        _ => {
+            msg.reply(
+                &ctx.http,
+                format!(
+                    "Use `!limits [user <n|off>|track <duração|off>|queue <duração|off>|live <on|off>|size <1-{}>|page <1-{}>]`.",
+                    playlist::MAX_PLAYLIST_LIMIT,
+                    playlist::MAX_PAGE_SIZE
+                ),
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let settings = match updated {
+        Some(Ok(settings)) => settings,
+        Some(Err(_)) => {
+            msg.reply(&ctx.http, "Não foi possível salvar a configuração.")
+                .await?;
+
+            return Ok(());
+        }
+        None => settings::get(guild_id).await,
+    };
+
+    let format_limit = |seconds: Option<u64>| match seconds {
+        Some(seconds) => format_duration(Some(Duration::from_secs(seconds))),
+        None => "Sem limite".to_string(),
+    };
+
+    let max_entries_per_user = match settings.max_entries_per_user {
+        Some(max) => max.to_string(),
+        None => "Sem limite".to_string(),
+    };
+
+    let live = if settings.block_live {
+        "Bloqueado"
+    } else {
+        "Permitido"
+    };
+
+    msg.reply(
+        &ctx.http,
+        format!(
+            "Tamanho da playlist: {}\nMúsicas por página: {}\nMúsicas por usuário: {}\nDuração máxima da música: {}\nDuração máxima da playlist: {}\nAo vivo: {}",
+            settings.playlist_limit(),
+            settings.page_size(),
+            max_entries_per_user,
+            format_limit(settings.max_track_duration),
+            format_limit(settings.max_queue_duration),
+            live,
+        ),
+    )
+    .await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn dj(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let role_id = match msg.mention_roles.first() {
+        Some(role_id) => Some(role_id.0),
+        None if args.rest().trim() == "off" => None,
+        None => {
+            let content = match settings::get(guild_id).await.dj_role_id {
+                Some(role_id) => format!("O cargo de DJ é <@&{role_id}>."),
+                None => "Nenhum cargo de DJ definido, use `!dj @cargo`.".to_string(),
+            };
+
+            msg.reply(&ctx.http, content).await?;
+
+            return Ok(());
+        }
+    };
+
+    let updated = settings::update(guild_id, |s| s.dj_role_id = role_id).await;
+
+    let content = match updated {
+        Ok(_) if role_id.is_some() => "Cargo de DJ definido.",
+        Ok(_) => "Cargo de DJ removido.",
+        Err(_) => "Não foi possível salvar a configuração.",
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    if updated.is_ok() {
+        moderation::record(
+            ctx,
+            GuildId(guild_id),
+            msg.channel_id,
+            LogEntry::new("Cargo de DJ alterado", msg.author.id).detail(
+                "Cargo",
+                role_id.map_or("Nenhum".to_string(), |role_id| format!("<@&{role_id}>")),
+            ),
+        )
+        .await;
     }
 
     Ok(())
@@ -246,10 +820,531 @@ pub async fn skip(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
 
 #[command]
 #[only_in(guilds)]
-pub async fn stop(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+pub async fn crossfade(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
     let guild_id = msg.guild_id.unwrap().0;
+    let value = args.rest().trim().to_lowercase();
 
-    player::stop(ctx, guild_id).await;
+    if value.is_empty() {
+        let content = match settings::get(guild_id).await.crossfade {
+            0 => "Crossfade desativado.".to_string(),
+            seconds => format!("Crossfade de {seconds}s entre as músicas."),
+        };
+
+        msg.reply(&ctx.http, content).await?;
+
+        return Ok(());
+    }
+
+    let member = msg.member(&ctx.http).await?;
+
+    if !player::is_dj(ctx, &member).await {
+        msg.reply(&ctx.http, "Apenas DJs podem mudar o crossfade.")
+            .await?;
+
+        return Ok(());
+    }
+
+    let seconds = match value.as_str() {
+        "off" => Some(0),
+        value => value
+            .trim_end_matches('s')
+            .parse::<u64>()
+            .ok()
+            .filter(|seconds| *seconds <= player::MAX_CROSSFADE),
+    };
+
+    let seconds = match seconds {
+        Some(seconds) => seconds,
+        None => {
+            msg.reply(
+                &ctx.http,
+                format!("Use `!crossfade <0-{}|off>`.", player::MAX_CROSSFADE),
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id, |s| s.crossfade = seconds).await {
+        Ok(_) if seconds == 0 => "Crossfade desativado.".to_string(),
+        Ok(_) => format!("Crossfade de {seconds}s definido, vale a partir da próxima música."),
+        Err(_) => "Não foi possível salvar a configuração.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn sfx(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+
+    let member = msg.member(&ctx.http).await?;
+    let is_manager = member
+        .permissions(&ctx.cache)
+        .map(|permissions| permissions.administrator() || permissions.manage_guild())
+        .unwrap_or(false);
+
+    let content = match action.as_str() {
+        "add" | "remove" | "volume" if !is_manager => {
+            "Apenas administradores podem gerenciar os efeitos sonoros.".to_string()
+        }
+        "add" => {
+            let name = args.single::<String>().unwrap_or_default();
+            let volume = match args.rest().trim() {
+                "" => Some(100),
+                volume => volume.parse::<u32>().ok().filter(|volume| *volume <= 100),
+            };
+
+            match (msg.attachments.first(), volume) {
+                (None, _) => "Anexe o arquivo de áudio do efeito.".to_string(),
+                (_, None) => "O volume deve ser entre 0 e 100.".to_string(),
+                (Some(attachment), Some(volume)) => {
+                    match soundboard::add(
+                        guild_id,
+                        msg.author.id.0,
+                        &name,
+                        attachment,
+                        volume as f32 / 100.,
+                    )
+                    .await
+                    {
+                        Ok(clip) => format!(
+                            "Efeito `{}` adicionado ({}).",
+                            clip.name,
+                            format_duration(Some(Duration::from_millis(clip.duration_ms)))
+                        ),
+                        Err(why) => soundboard_error_message(why),
+                    }
+                }
+            }
+        }
+        "remove" => match soundboard::remove(guild_id, args.rest()).await {
+            Ok(_) => format!("Efeito `{}` removido.", args.rest().trim()),
+            Err(why) => soundboard_error_message(why),
+        },
+        "volume" => {
+            let name = args.single::<String>().unwrap_or_default();
+
+            match args.rest().trim().parse::<u32>() {
+                Ok(volume) if volume <= 100 => {
+                    match soundboard::set_volume(guild_id, &name, volume as f32 / 100.).await {
+                        Ok(_) => format!("Volume do efeito `{name}` mudado."),
+                        Err(why) => soundboard_error_message(why),
+                    }
+                }
+                _ => "O volume deve ser entre 0 e 100.".to_string(),
+            }
+        }
+        "list" => match soundboard::list(guild_id).await {
+            Ok(clips) if clips.is_empty() => "Nenhum efeito sonoro cadastrado.".to_string(),
+            Ok(clips) => format!(
+                "**Efeitos sonoros ({}/{}):**\n{}",
+                clips.len(),
+                soundboard::MAX_CLIPS,
+                clips
+                    .iter()
+                    .map(|clip| format!("`{}`", clip.name))
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+            Err(why) => soundboard_error_message(why),
+        },
+        "" => "Use `!sfx <nome>` ou `!sfx add|remove|volume|list`.".to_string(),
+        name => match soundboard::play(ctx, guild_id, name).await {
+            Ok(_) => return Ok(()),
+            Err(why) => soundboard_error_message(why),
+        },
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+fn soundboard_error_message(error: SoundboardError) -> String {
+    match error {
+        SoundboardError::InvalidName => {
+            "O nome do efeito deve ter até 32 letras, números, `-` ou `_`.".to_string()
+        }
+        SoundboardError::InvalidFile => {
+            "Arquivo inválido, envie um mp3, ogg, opus, wav, m4a ou webm.".to_string()
+        }
+        SoundboardError::TooLarge => "O arquivo é grande demais.".to_string(),
+        SoundboardError::TooLong => format!(
+            "O efeito deve ter no máximo {} segundos.",
+            soundboard::MAX_CLIP_LENGTH.as_secs()
+        ),
+        SoundboardError::LimitReached => "O limite de efeitos sonoros foi atingido.".to_string(),
+        SoundboardError::AlreadyExists => "Já existe um efeito com esse nome.".to_string(),
+        SoundboardError::NotFound => "Efeito não encontrado.".to_string(),
+        SoundboardError::Cooldown(remaining) => format!(
+            "Aguarde {}s para tocar outro efeito.",
+            remaining.as_secs() + 1
+        ),
+        SoundboardError::NotConnected => "Não estou em nenhum canal de voz.".to_string(),
+        SoundboardError::Storage => "Não foi possível acessar o arquivo do efeito.".to_string(),
+        SoundboardError::Database => "Não foi possível acessar os efeitos sonoros.".to_string(),
+    }
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn say(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+    let text = content_safe(
+        &ctx.cache,
+        args.rest(),
+        &ContentSafeOptions::default(),
+        &msg.mentions,
+    );
+
+    let content = match speech::say(ctx, guild_id, &text).await {
+        Ok(_) => return Ok(()),
+        Err(SpeechError::Empty) => "Use `!say <texto>`.".to_string(),
+        Err(SpeechError::TooLong) => format!(
+            "O texto deve ter no máximo {} caracteres.",
+            speech::MAX_SPEECH_LENGTH
+        ),
+        Err(SpeechError::NotConnected) => "Não estou em nenhum canal de voz.".to_string(),
+        Err(SpeechError::Synthesis) => "Não foi possível gerar o áudio.".to_string(),
+        Err(SpeechError::Cooldown(remaining)) => format!(
+            "Aguarde {}s para usar o `!say` de novo.",
+            remaining.as_secs() + 1
+        ),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn announce(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let enabled = match args.rest().trim().to_lowercase().as_str() {
+        "on" => true,
+        "off" => false,
+        _ => {
+            let content = if settings::get(guild_id).await.announcements {
+                "Anúncios por voz ativados, use `!announce off` para desativar."
+            } else {
+                "Anúncios por voz desativados, use `!announce on` para ativar."
+            };
+
+            msg.reply(&ctx.http, content).await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id, |s| s.announcements = enabled).await {
+        Ok(_) if enabled => "Anúncios por voz ativados.",
+        Ok(_) => "Anúncios por voz desativados.",
+        Err(_) => "Não foi possível salvar a configuração.",
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn join(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let member = msg.member(&ctx.http).await?;
+
+    let channel_id = match args.rest().trim() {
+        "" => player::member_channel(ctx, guild_id, &member),
+        name => parse_voice_channel(ctx, msg, name).await,
+    };
+
+    let channel_id = match channel_id {
+        Some(channel_id) => channel_id,
+        None => {
+            msg.reply(
+                &ctx.http,
+                "Entre em um canal de voz ou informe um canal válido.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    match player::current_channel(ctx, guild_id).await {
+        Some(current) if current == channel_id => {
+            msg.reply(&ctx.http, "Já estou nesse canal.").await?;
+
+            return Ok(());
+        }
+        Some(_) if !player::is_dj(ctx, &member).await => {
+            msg.reply(
+                &ctx.http,
+                "Já estou em outro canal, apenas DJs podem me mover.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+        _ => {}
+    }
+
+    connect(ctx, msg, guild_id, channel_id).await
+}
+
+#[command("move")]
+#[only_in(guilds)]
+pub async fn move_to(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let member = msg.member(&ctx.http).await?;
+
+    if !player::is_dj(ctx, &member).await {
+        msg.reply(&ctx.http, "Apenas DJs podem me mover.").await?;
+
+        return Ok(());
+    }
+
+    let channel_id = match parse_voice_channel(ctx, msg, args.rest().trim()).await {
+        Some(channel_id) => channel_id,
+        None => {
+            msg.reply(&ctx.http, "Use `!move <canal de voz>`.").await?;
+
+            return Ok(());
+        }
+    };
+
+    connect(ctx, msg, guild_id, channel_id).await
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn leave(ctx: &Context, msg: &Message, _args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let member = msg.member(&ctx.http).await?;
+
+    if !player::is_dj(ctx, &member).await {
+        msg.reply(&ctx.http, "Apenas DJs podem me desconectar.")
+            .await?;
+
+        return Ok(());
+    }
+
+    if player::current_channel(ctx, guild_id).await.is_none() {
+        msg.reply(&ctx.http, "Não estou em nenhum canal de voz.")
+            .await?;
+
+        return Ok(());
+    }
+
+    player::leave(ctx, guild_id.0).await;
+
+    if settings::get(guild_id.0).await.stay_connected {
+        msg.reply(
+            &ctx.http,
+            "Saí do canal. O modo 24/7 continua ativo, use `!247 off` para desativar.",
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+#[command("247")]
+#[only_in(guilds)]
+pub async fn stay(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap();
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+
+    match action.as_str() {
+        "on" => {
+            let member = msg.member(&ctx.http).await?;
+
+            let channel_id = match args.rest().trim() {
+                "" => match player::current_channel(ctx, guild_id).await {
+                    Some(channel_id) => Some(channel_id),
+                    None => player::member_channel(ctx, guild_id, &member),
+                },
+                name => parse_voice_channel(ctx, msg, name).await,
+            };
+
+            let channel_id = match channel_id {
+                Some(channel_id) => channel_id,
+                None => {
+                    msg.reply(&ctx.http, "Use `!247 on <canal de voz>`.")
+                        .await?;
+
+                    return Ok(());
+                }
+            };
+
+            let updated = settings::update(guild_id.0, |s| {
+                s.stay_connected = true;
+                s.voice_channel_id = Some(channel_id.0);
+            })
+            .await;
+
+            if updated.is_err() {
+                msg.reply(&ctx.http, "Não foi possível salvar a configuração.")
+                    .await?;
+
+                return Ok(());
+            }
+
+            if player::current_channel(ctx, guild_id).await != Some(channel_id) {
+                if let Err(why) = player::join(ctx, guild_id, channel_id).await {
+                    msg.reply(&ctx.http, why.to_string()).await?;
+
+                    return Ok(());
+                }
+            }
+
+            msg.reply(
+                &ctx.http,
+                format!("Modo 24/7 ativado, vou ficar em <#{}>.", channel_id.0),
+            )
+            .await?;
+        }
+        "off" => {
+            let content = match settings::update(guild_id.0, |s| s.stay_connected = false).await {
+                Ok(_) => "Modo 24/7 desativado.",
+                Err(_) => "Não foi possível salvar a configuração.",
+            };
+
+            msg.reply(&ctx.http, content).await?;
+        }
+        _ => {
+            let settings = settings::get(guild_id.0).await;
+
+            let content = match settings.voice_channel_id {
+                Some(channel_id) if settings.stay_connected => {
+                    format!("Modo 24/7 ativo em <#{channel_id}>.")
+                }
+                _ => "Modo 24/7 desativado, use `!247 on [canal]` para ativar.".to_string(),
+            };
+
+            msg.reply(&ctx.http, content).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn connect(
+    ctx: &Context,
+    msg: &Message,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> CommandResult {
+    let speaker_status = match player::join(ctx, guild_id, channel_id).await {
+        Ok(speaker_status) => speaker_status,
+        Err(why) => {
+            msg.reply(&ctx.http, why.to_string()).await?;
+
+            return Ok(());
+        }
+    };
+
+    if settings::get(guild_id.0).await.stay_connected {
+        let _ = settings::update(guild_id.0, |s| s.voice_channel_id = Some(channel_id.0)).await;
+    }
+
+    let mut content = format!("Conectado em <#{}>.", channel_id.0);
+
+    if speaker_status == SpeakerStatus::Requested {
+        content.push_str(&format!(" {}", stage::REQUESTED_TO_SPEAK));
+    }
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+async fn parse_voice_channel(ctx: &Context, msg: &Message, name: &str) -> Option<ChannelId> {
+    if name.is_empty() {
+        return None;
+    }
+
+    let channel = GuildChannel::convert(ctx, msg.guild_id, Some(msg.channel_id), name)
+        .await
+        .ok()?;
+
+    let is_voice = matches!(channel.kind, ChannelType::Voice | ChannelType::Stage);
+
+    if !is_voice || Some(channel.guild_id) != msg.guild_id {
+        return None;
+    }
+
+    Some(channel.id)
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn stagetopic(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let enabled = match args.rest().trim().to_lowercase().as_str() {
+        "on" => true,
+        "off" => false,
+        _ => {
+            let content = if settings::get(guild_id).await.stage_topic {
+                "O tópico do palco mostra a música atual, use `!stagetopic off` para desativar."
+            } else {
+                "O tópico do palco não é alterado, use `!stagetopic on` para mostrar a música atual."
+            };
+
+            msg.reply(&ctx.http, content).await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id, |s| s.stage_topic = enabled).await {
+        Ok(_) if enabled => "O tópico do palco vai mostrar a música atual.",
+        Ok(_) => "O tópico do palco não será mais alterado.",
+        Err(_) => "Não foi possível salvar a configuração.",
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn fairqueue(ctx: &Context, msg: &Message, args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let enabled = match args.rest().trim().to_lowercase().as_str() {
+        "on" => true,
+        "off" => false,
+        _ => {
+            let content = if settings::get(guild_id).await.fair_queue {
+                "A fila justa está ativada, use `!fairqueue off` para tocar na ordem de chegada."
+            } else {
+                "A fila toca na ordem de chegada, use `!fairqueue on` para alternar entre quem pediu."
+            };
+
+            msg.reply(&ctx.http, content).await?;
+
+            return Ok(());
+        }
+    };
+
+    let content = match settings::update(guild_id, |s| s.fair_queue = enabled).await {
+        Ok(_) if enabled => "Fila justa ativada, as músicas vão alternar entre quem pediu.",
+        Ok(_) => "Fila justa desativada, as músicas vão tocar na ordem de chegada.",
+        Err(_) => "Não foi possível salvar a configuração.",
+    };
+
+    msg.reply(&ctx.http, content).await?;
 
     Ok(())
 }
@@ -18,7 +18,7 @@ use serenity::{
         prelude::{Channel, ChannelId, Embed, Guild, GuildId, Member},
         user::User,
     },
-    prelude::{ClientError, Context},
+    prelude::{ClientError, Context, TypeMapKey},
     utils::{CustomMessage, MessageBuilder},
 };
 use songbird::{
@@ -30,29 +30,130 @@ use songbird::{
 use tokio::sync::Mutex;
 
 use super::{
-    handler::{self, StopMusicHandle},
-    playlist::{self, PlaylistError},
-    query::query_video,
-    send_media_message,
+    handler::{self, CrossfadeHandle, PrepareNextHandle, StopMusicHandle},
+    playlist::{self, Placement, PlaylistError, QueueLimits},
+    query::{query_video, SearchSource},
+    send_media_message, speech,
+    stage::{self, SpeakerStatus},
 };
+use crate::settings::{self, GuildSettings};
 
 type PlayerResult<T> = Result<T, PlayerError>;
 
+const PREBUFFER_LEAD: Duration = Duration::from_secs(15);
+const CROSSFADE_STEP: Duration = Duration::from_millis(100);
+pub const MAX_CROSSFADE: u64 = 10;
+
+struct PreparedTrack {
+    entry_id: u64,
+    handle: TrackHandle,
+    member: Member,
+    channel_id: ChannelId,
+}
+
 lazy_static! {
     pub static ref CURRENT_TRACKS: Mutex<HashMap<u64, Arc<TrackHandle>>> =
         Mutex::new(HashMap::new());
+    static ref PREPARED_TRACKS: Mutex<HashMap<u64, PreparedTrack>> = Mutex::new(HashMap::new());
+    static ref VOLUMES: Mutex<HashMap<u64, f32>> = Mutex::new(HashMap::new());
 }
 
 #[derive(Debug, Clone)]
 pub enum PlayerStatus {
     Playing(Arc<TrackHandle>),
-    Queued,
+    Queued(QueuePosition),
+    Skipped,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QueuePosition {
+    pub position: usize,
+    pub starts_in: Option<Duration>,
+}
+
+impl std::fmt::Display for QueuePosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.starts_in {
+            Some(starts_in) => write!(
+                f,
+                "posição #{}, toca em ~{}",
+                self.position,
+                format_duration(Some(starts_in))
+            ),
+            None => write!(
+                f,
+                "posição #{}, sem previsão por causa de uma transmissão ao vivo",
+                self.position
+            ),
+        }
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
 pub enum PlayerError {
     UserOffVoiceChannel,
     MusicNotFound,
+    PlaylistFull,
+    LiveNotAllowed,
+    TrackTooLong(Duration),
+    UserQuotaReached(usize),
+    QueueTooLong(Duration),
+    JoinFailed,
+    StageNoPermission,
+    NotDj,
+}
+
+impl std::fmt::Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PlayerError::UserOffVoiceChannel => write!(f, "Você precisa estar em um canal de voz."),
+            PlayerError::MusicNotFound => write!(f, "Musica não encontrada."),
+            PlayerError::PlaylistFull => write!(f, "A playlist está cheia."),
+            PlayerError::LiveNotAllowed => {
+                write!(f, "Transmissões ao vivo não são permitidas neste servidor.")
+            }
+            PlayerError::TrackTooLong(max) => write!(
+                f,
+                "A música ultrapassa a duração máxima de {}.",
+                format_duration(Some(*max))
+            ),
+            PlayerError::UserQuotaReached(max) => {
+                write!(
+                    f,
+                    "Você já tem {max} músicas na playlist, aguarde elas tocarem."
+                )
+            }
+            PlayerError::QueueTooLong(max) => write!(
+                f,
+                "A playlist ultrapassaria a duração máxima de {}.",
+                format_duration(Some(*max))
+            ),
+            PlayerError::JoinFailed => write!(f, "Não foi possível entrar no canal de voz."),
+            PlayerError::NotDj => write!(f, "Apenas DJs podem usar este comando."),
+            PlayerError::StageNoPermission => write!(
+                f,
+                "Não tenho permissão para falar neste palco, preciso de \"Silenciar membros\" ou \"Pedir para falar\"."
+            ),
+        }
+    }
+}
+
+pub struct Requester;
+
+impl TypeMapKey for Requester {
+    type Value = Member;
+}
+
+pub struct Superseded;
+
+impl TypeMapKey for Superseded {
+    type Value = bool;
+}
+
+pub struct NowPlaying {
+    pub media_info: MediaInfo,
+    pub position: Duration,
+    pub requester: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -63,6 +164,7 @@ pub struct MediaInfo {
     pub video_duration: Option<Duration>,
     pub url: String,
     pub duration: String,
+    pub origin: SearchSource,
 }
 
 pub fn format_duration(duration: Option<Duration>) -> String {
@@ -79,10 +181,17 @@ pub fn format_duration(duration: Option<Duration>) -> String {
     );
 
     if naive_duration.hour() > 0 {
-        return format!("{}", naive_duration.format("%H:%M:%S").to_string());
+        return naive_duration.format("%H:%M:%S").to_string();
     }
 
-    format!("{}", naive_duration.format("%M:%S").to_string())
+    naive_duration.format("%M:%S").to_string()
+}
+
+pub fn format_date(date: Option<&str>) -> String {
+    match date.and_then(|date| chrono::NaiveDate::parse_from_str(date, "%Y%m%d").ok()) {
+        Some(date) => date.format("%d/%m/%Y").to_string(),
+        None => String::from("Desconhecida"),
+    }
 }
 
 pub async fn add(
@@ -91,47 +200,93 @@ pub async fn add(
     guild_id: GuildId,
     channel_id: ChannelId,
     member: Member,
+    placement: Placement,
 ) -> PlayerResult<PlayerStatus> {
-    let source = query_video(uri.clone()).await;
+    let bypass_limits = is_dj(&ctx, &member).await;
+
+    if placement != Placement::Back && !bypass_limits {
+        return Err(PlayerError::NotDj);
+    }
+
+    let settings = settings::get(guild_id.0).await;
+    let source = query_video(uri.clone(), settings.search_source).await;
 
     if source.is_err() {
         return Err(PlayerError::MusicNotFound);
     }
 
-    let source = source.unwrap();
+    let (source, origin) = source.unwrap();
+
+    if !bypass_limits {
+        match source.metadata.duration {
+            None if settings.block_live => return Err(PlayerError::LiveNotAllowed),
+            Some(duration) => {
+                if let Some(max) = settings.max_track_duration.map(Duration::from_secs) {
+                    if duration > max {
+                        return Err(PlayerError::TrackTooLong(max));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
 
-    let current_track = CURRENT_TRACKS.lock().await.get(&guild_id.0);
     let mut can_play = true;
 
-    match CURRENT_TRACKS.lock().await.get(&guild_id.0) {
-        Some(track_handle) => {
-            can_play = match track_handle.get_info().await.unwrap().playing {
-                PlayMode::End | PlayMode::Stop => true,
-                _ => false,
-            };
-        }
-        _ => {}
-    };
+    if let Some(track_handle) = CURRENT_TRACKS.lock().await.get(&guild_id.0) {
+        can_play = matches!(
+            track_handle.get_info().await.unwrap().playing,
+            PlayMode::End | PlayMode::Stop
+        );
+    }
 
     if !can_play {
-        playlist::insert(
+        if placement != Placement::Back {
+            unprepare(guild_id.0).await;
+        }
+
+        let inserted = playlist::insert(
             guild_id.0,
-            playlist::PlaylistItem {
-                ctx: ctx.clone(),
-                uri: uri.clone(),
+            playlist::PlaylistItem::new(
+                ctx.clone(),
+                uri.clone(),
                 guild_id,
                 channel_id,
                 member,
                 source,
-            },
+                origin,
+            ),
+            queue_limits(&settings, bypass_limits),
+            placement,
         )
-        .await
-        .unwrap();
+        .await;
 
-        return Ok(PlayerStatus::Queued);
+        let index = match inserted {
+            Ok(index) => index,
+            Err(PlaylistError::UserQuotaReached(max)) => {
+                return Err(PlayerError::UserQuotaReached(max))
+            }
+            Err(PlaylistError::QueueTooLong(max)) => return Err(PlayerError::QueueTooLong(max)),
+            Err(_) => return Err(PlayerError::PlaylistFull),
+        };
+
+        if placement == Placement::Skip {
+            next(&ctx, guild_id.0, channel_id.0).await;
+
+            return Ok(PlayerStatus::Skipped);
+        }
+
+        let starts_in = playlist::info(guild_id.0, index + 1, 1)
+            .await
+            .and_then(|info| info.items.first().and_then(|item| item.starts_in));
+
+        return Ok(PlayerStatus::Queued(QueuePosition {
+            position: index + 1,
+            starts_in,
+        }));
     }
 
-    let track_handle = play(&ctx, source, guild_id, channel_id, member).await?;
+    let track_handle = play(&ctx, source, origin, guild_id, channel_id, member).await?;
 
     let track_handler = Arc::new(track_handle);
 
@@ -143,9 +298,43 @@ pub async fn add(
     Ok(PlayerStatus::Playing(track_handler.clone()))
 }
 
+pub async fn is_dj(ctx: &Context, member: &Member) -> bool {
+    let is_manager = member
+        .permissions(&ctx.cache)
+        .map(|permissions| permissions.administrator() || permissions.manage_guild())
+        .unwrap_or(false);
+
+    if is_manager {
+        return true;
+    }
+
+    match settings::get(member.guild_id.0).await.dj_role_id {
+        Some(role_id) => member.roles.iter().any(|role| role.0 == role_id),
+        None => false,
+    }
+}
+
+fn queue_limits(settings: &GuildSettings, bypass_limits: bool) -> QueueLimits {
+    let max_entries = settings.playlist_limit();
+
+    if bypass_limits {
+        return QueueLimits {
+            max_entries,
+            ..QueueLimits::default()
+        };
+    }
+
+    QueueLimits {
+        max_entries,
+        max_entries_per_user: settings.max_entries_per_user,
+        max_queue_duration: settings.max_queue_duration.map(Duration::from_secs),
+    }
+}
+
 pub async fn play(
     ctx: &Context,
     source: Input,
+    origin: SearchSource,
     guild_id: GuildId,
     channel_id: ChannelId,
     member: Member,
@@ -163,23 +352,61 @@ pub async fn play(
 
     let connect_to = voice_channel_id.unwrap();
 
-    let manager = songbird::get(&ctx)
+    let manager = songbird::get(ctx)
         .await
         .expect("Songbird Voice client placed in at initialization.")
         .clone();
 
-    let _ = manager.join(guild_id, connect_to).await;
+    if current_channel(ctx, guild_id).await.is_none() {
+        match join(ctx, guild_id, connect_to).await {
+            Ok(SpeakerStatus::Requested) => {
+                let _ = channel_id.say(&ctx.http, stage::REQUESTED_TO_SPEAK).await;
+            }
+            Ok(_) => {}
+            // Continua conectado, um moderador ainda pode liberar a fala.
+            Err(PlayerError::StageNoPermission) => {
+                let _ = channel_id
+                    .say(&ctx.http, PlayerError::StageNoPermission.to_string())
+                    .await;
+            }
+            Err(why) => return Err(why),
+        }
+    }
 
     let mut handler = match manager.get(guild_id) {
         Some(handler) => handler.lock_owned().await,
         _ => return Err(PlayerError::UserOffVoiceChannel),
     };
 
-    let (track, track_handle) = create_player(source);
+    let (mut track, track_handle) = create_player(source);
+
+    track.set_volume(effective_volume(guild_id.0).await);
 
-    handler.play_only(track);
+    {
+        let mut typemap = track_handle.typemap().write().await;
+
+        typemap.insert::<SearchSource>(origin);
+        typemap.insert::<Requester>(member.clone());
+    }
+
+    // Toca junto com falas e efeitos sonoros; a música anterior já foi parada.
+    handler.play(track);
     handler.deafen(true).await.unwrap();
 
+    let crossfade = crossfade_duration(guild_id.0).await;
+
+    add_track_events(ctx, &track_handle, guild_id, channel_id, crossfade);
+
+    Ok(track_handle)
+}
+
+fn add_track_events(
+    ctx: &Context,
+    track_handle: &TrackHandle,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    crossfade: Duration,
+) {
     track_handle
         .add_event(
             songbird::Event::Track(TrackEvent::End),
@@ -191,60 +418,404 @@ pub async fn play(
         )
         .unwrap();
 
-    Ok(track_handle)
+    let duration = match track_handle.metadata().duration {
+        Some(duration) => duration,
+        None => return,
+    };
+
+    let _ = track_handle.add_event(
+        songbird::Event::Delayed(duration.saturating_sub(PREBUFFER_LEAD.max(crossfade))),
+        PrepareNextHandle {
+            ctx: ctx.clone(),
+            guild_id,
+        },
+    );
+
+    if crossfade.is_zero() {
+        return;
+    }
+
+    let _ = track_handle.add_event(
+        songbird::Event::Delayed(duration.saturating_sub(crossfade)),
+        CrossfadeHandle {
+            ctx: ctx.clone(),
+            guild_id,
+        },
+    );
 }
 
-pub async fn next(ctx: &Context, guild_id: u64, channel_id: u64) -> bool {
-    let manager = songbird::get(&ctx).await.unwrap();
-    let next_item = playlist::next(guild_id).await;
+async fn crossfade_duration(guild_id: u64) -> Duration {
+    Duration::from_secs(settings::get(guild_id).await.crossfade.min(MAX_CROSSFADE))
+}
 
-    if next_item.is_none() {
-        CURRENT_TRACKS.lock().await.remove(&guild_id);
+pub async fn prepare_next(ctx: &Context, guild_id: u64) {
+    if PREPARED_TRACKS.lock().await.contains_key(&guild_id) {
+        return;
+    }
 
-        let handler_lock = manager.get(guild_id);
+    let manager = songbird::get(ctx).await.unwrap();
 
-        if handler_lock.is_none() {
-            return false;
-        }
+    let handler_lock = match manager.get(guild_id) {
+        Some(handler_lock) => handler_lock,
+        None => return,
+    };
 
-        let mut handler = handler_lock.as_ref().unwrap().lock().await;
+    // Só uma chamada consegue levar a fonte da entrada, então preparos
+    // concorrentes não criam duas faixas.
+    let item = match playlist::prepare(guild_id).await {
+        Some(item) => item,
+        None => return,
+    };
 
-        let on_channel = handler.current_channel().is_some();
-        let has_connection = handler.current_connection().is_some();
+    let crossfade = crossfade_duration(guild_id).await;
+    let (mut track, track_handle) = create_player(item.source);
 
-        if has_connection {
-            handler.stop();
-        }
+    track.pause();
 
-        if on_channel {
-            handler.leave().await.unwrap();
-        }
+    {
+        let mut typemap = track_handle.typemap().write().await;
 
-        return false;
+        typemap.insert::<SearchSource>(item.origin);
+        typemap.insert::<Requester>(item.member.clone());
     }
 
-    let item = next_item.unwrap();
-
-    let track_handle = play(
+    add_track_events(
         &item.ctx,
-        item.source,
+        &track_handle,
         item.guild_id,
         item.channel_id,
-        item.member.clone(),
-    )
-    .await
-    .unwrap();
+        crossfade,
+    );
 
-    let track_handler = Arc::new(track_handle);
+    handler_lock.lock().await.play(track);
+
+    PREPARED_TRACKS.lock().await.insert(
+        guild_id,
+        PreparedTrack {
+            entry_id: item.id,
+            handle: track_handle,
+            member: item.member,
+            channel_id: item.channel_id,
+        },
+    );
+}
+
+pub async fn crossfade(ctx: &Context, guild_id: u64) {
+    prepare_next(ctx, guild_id).await;
+
+    let prepared = match take_prepared(guild_id).await {
+        Some(prepared) => prepared,
+        None => return,
+    };
+
+    let fade = crossfade_duration(guild_id).await;
+    let outgoing = retire_current(guild_id).await;
+
+    let _ = prepared.handle.set_volume(0.);
+    let _ = prepared.handle.play();
+
+    let incoming = Arc::new(prepared.handle);
 
     CURRENT_TRACKS
         .lock()
         .await
-        .insert(guild_id, track_handler.clone());
+        .insert(guild_id, incoming.clone());
+
+    send_media_message(ctx, &prepared.member, prepared.channel_id, incoming.clone()).await;
+    announce(ctx, guild_id, &prepared.member, &incoming).await;
 
-    send_media_message(&ctx, &item.member, item.channel_id, track_handler.clone()).await;
+    tokio::spawn(async move {
+        let steps = (fade.as_millis() / CROSSFADE_STEP.as_millis()).max(1) as u32;
 
-    return true;
+        for step in 1..=steps {
+            let progress = step as f32 / steps as f32;
+            // Lido a cada passo para respeitar falas e mudanças de volume no meio.
+            let volume = effective_volume(guild_id).await;
+
+            let _ = incoming.set_volume(volume * progress);
+
+            if let Some(outgoing) = &outgoing {
+                let _ = outgoing.set_volume(volume * (1. - progress));
+            }
+
+            tokio::time::sleep(CROSSFADE_STEP).await;
+        }
+
+        if let Some(outgoing) = outgoing {
+            let _ = outgoing.stop();
+        }
+    });
+}
+
+async fn announce(ctx: &Context, guild_id: u64, member: &Member, track_handle: &TrackHandle) {
+    if !settings::get(guild_id).await.announcements {
+        return;
+    }
+
+    let title = track_handle
+        .metadata()
+        .title
+        .clone()
+        .unwrap_or("???".to_string());
+
+    let text = format!(
+        "Tocando agora: {title}, pedido por {}",
+        member.display_name()
+    )
+    .chars()
+    .take(speech::MAX_SPEECH_LENGTH)
+    .collect::<String>();
+
+    let ctx = ctx.clone();
+
+    tokio::spawn(async move {
+        let _ = speech::speak(&ctx, guild_id, &text).await;
+    });
+}
+
+// O volume escolhido pelo usuário fica separado do volume real da faixa, que
+// também é abaixado durante falas e variado pelo crossfade.
+pub async fn target_volume(guild_id: u64) -> f32 {
+    VOLUMES.lock().await.get(&guild_id).copied().unwrap_or(1.)
+}
+
+async fn effective_volume(guild_id: u64) -> f32 {
+    let volume = target_volume(guild_id).await;
+
+    if speech::is_ducked(guild_id).await {
+        return volume * speech::DUCK_VOLUME;
+    }
+
+    volume
+}
+
+pub async fn apply_volume(guild_id: u64) {
+    let volume = effective_volume(guild_id).await;
+
+    if let Some(track_handle) = CURRENT_TRACKS.lock().await.get(&guild_id) {
+        let _ = track_handle.set_volume(volume);
+    }
+}
+
+async fn supersede(track_handle: &TrackHandle) {
+    track_handle
+        .typemap()
+        .write()
+        .await
+        .insert::<Superseded>(true);
+}
+
+pub async fn is_superseded(track_handle: &TrackHandle) -> bool {
+    track_handle
+        .typemap()
+        .read()
+        .await
+        .get::<Superseded>()
+        .copied()
+        .unwrap_or(false)
+}
+
+// A entrada continua na fila; sem a faixa preparada, ela é resolvida de novo
+// quando chegar a vez dela.
+async fn unprepare(guild_id: u64) {
+    let prepared = PREPARED_TRACKS.lock().await.remove(&guild_id);
+
+    if let Some(prepared) = prepared {
+        discard(prepared).await;
+    }
+}
+
+async fn discard(prepared: PreparedTrack) {
+    supersede(&prepared.handle).await;
+
+    let _ = prepared.handle.stop();
+}
+
+async fn take_prepared(guild_id: u64) -> Option<PreparedTrack> {
+    let prepared = PREPARED_TRACKS.lock().await.remove(&guild_id)?;
+
+    if playlist::next_if(guild_id, prepared.entry_id)
+        .await
+        .is_none()
+    {
+        discard(prepared).await;
+
+        return None;
+    }
+
+    Some(prepared)
+}
+
+async fn retire_current(guild_id: u64) -> Option<Arc<TrackHandle>> {
+    let track_handle = CURRENT_TRACKS.lock().await.remove(&guild_id)?;
+
+    supersede(&track_handle).await;
+
+    Some(track_handle)
+}
+
+pub async fn next(ctx: &Context, guild_id: u64, channel_id: u64) -> bool {
+    let manager = songbird::get(ctx).await.unwrap();
+    if let Some(previous) = retire_current(guild_id).await {
+        let _ = previous.stop();
+    }
+
+    if let Some(prepared) = take_prepared(guild_id).await {
+        let _ = prepared.handle.set_volume(effective_volume(guild_id).await);
+        let _ = prepared.handle.play();
+
+        let track_handler = Arc::new(prepared.handle);
+
+        CURRENT_TRACKS
+            .lock()
+            .await
+            .insert(guild_id, track_handler.clone());
+
+        send_media_message(
+            ctx,
+            &prepared.member,
+            prepared.channel_id,
+            track_handler.clone(),
+        )
+        .await;
+        announce(ctx, guild_id, &prepared.member, &track_handler).await;
+
+        return true;
+    }
+
+    while let Some(mut item) = playlist::next(guild_id).await {
+        let source = match item.source.take() {
+            Some(source) => Some(source),
+            None => query_video(item.uri.clone(), item.origin)
+                .await
+                .ok()
+                .map(|(source, _)| source),
+        };
+
+        let source = match source {
+            Some(source) => source,
+            None => continue,
+        };
+
+        let played = play(
+            &item.ctx,
+            source,
+            item.origin,
+            item.guild_id,
+            item.channel_id,
+            item.member.clone(),
+        )
+        .await;
+
+        // Avisa e segue para a próxima música em vez de travar a fila.
+        let track_handle = match played {
+            Ok(track_handle) => track_handle,
+            Err(why) => {
+                let _ = item.channel_id.say(&ctx.http, why.to_string()).await;
+
+                continue;
+            }
+        };
+
+        let track_handler = Arc::new(track_handle);
+
+        CURRENT_TRACKS
+            .lock()
+            .await
+            .insert(guild_id, track_handler.clone());
+
+        send_media_message(ctx, &item.member, item.channel_id, track_handler.clone()).await;
+        announce(ctx, guild_id, &item.member, &track_handler).await;
+
+        return true;
+    }
+
+    let handler_lock = manager.get(guild_id);
+
+    if handler_lock.is_none() {
+        return false;
+    }
+
+    let stay_connected = settings::get(guild_id).await.stay_connected;
+    let mut handler = handler_lock.as_ref().unwrap().lock().await;
+
+    let on_channel = handler.current_channel().is_some() && !stay_connected;
+    let has_connection = handler.current_connection().is_some();
+
+    if has_connection {
+        handler.stop();
+    }
+
+    if on_channel {
+        handler.leave().await.unwrap();
+    }
+
+    false
+}
+
+pub async fn now_playing(guild_id: u64) -> Option<MediaInfo> {
+    let track_handle = CURRENT_TRACKS.lock().await.get(&guild_id)?.clone();
+
+    let metadata = track_handle.metadata().clone();
+    let origin = track_handle
+        .typemap()
+        .read()
+        .await
+        .get::<SearchSource>()
+        .copied()
+        .unwrap_or_default();
+
+    Some(MediaInfo {
+        title: metadata.title.unwrap_or("???".to_string()),
+        thumb: metadata.thumbnail.unwrap_or("???".to_string()),
+        artist: metadata
+            .artist
+            .or(metadata.channel)
+            .unwrap_or("???".to_string()),
+        video_duration: metadata.duration,
+        url: metadata.source_url?,
+        duration: format_duration(metadata.duration),
+        origin,
+    })
+}
+
+pub async fn current_remaining(guild_id: u64) -> Option<Duration> {
+    let track_handle = match CURRENT_TRACKS.lock().await.get(&guild_id) {
+        Some(track_handle) => track_handle.clone(),
+        None => return Some(Duration::ZERO),
+    };
+
+    let position = match track_handle.get_info().await {
+        Ok(info) => info.position,
+        Err(_) => return Some(Duration::ZERO),
+    };
+
+    let duration = track_handle.metadata().duration?;
+
+    Some(duration.saturating_sub(position))
+}
+
+pub async fn now_playing_info(guild_id: u64) -> Option<NowPlaying> {
+    let media_info = now_playing(guild_id).await?;
+    let track_handle = CURRENT_TRACKS.lock().await.get(&guild_id)?.clone();
+
+    let position = track_handle
+        .get_info()
+        .await
+        .map(|info| info.position)
+        .unwrap_or_default();
+    let requester = track_handle
+        .typemap()
+        .read()
+        .await
+        .get::<Requester>()
+        .map(|member| member.display_name().to_string());
+
+    Some(NowPlaying {
+        media_info,
+        position,
+        requester,
+    })
 }
 
 pub async fn pause(ctx: &Context, guild_id: GuildId) {
@@ -279,9 +850,9 @@ pub async fn trackinfo(ctx: &Context, guild_id: GuildId) -> String {
 
     let info = track_handle.get_info().await.unwrap();
 
-    let position = Some(info.position.clone());
-    let duration = track_handle.metadata().duration.clone();
-    let volume = info.volume.clone();
+    let position = Some(info.position);
+    let duration = track_handle.metadata().duration;
+    let volume = target_volume(guild_id.0).await;
 
     format!(
         "Duração: {}/{}\nVolume: {}/100",
@@ -292,14 +863,13 @@ pub async fn trackinfo(ctx: &Context, guild_id: GuildId) -> String {
 }
 
 pub async fn volume(ctx: &Context, guild_id: GuildId, new_volume: f32) -> String {
-    let current_track = CURRENT_TRACKS.lock().await;
+    if !CURRENT_TRACKS.lock().await.contains_key(&guild_id.0) {
+        return "Não há nenhuma música tocando.".to_string();
+    }
 
-    let track_handle = match current_track.get(&guild_id.0) {
-        Some(track_handle) => track_handle,
-        _ => return "Não há nenhuma música tocando.".to_string(),
-    };
+    VOLUMES.lock().await.insert(guild_id.0, new_volume);
 
-    track_handle.set_volume(new_volume);
+    apply_volume(guild_id.0).await;
 
     "Volume mudado.".to_string()
 }
@@ -326,11 +896,21 @@ pub async fn repeat(ctx: &Context, guild_id: GuildId, channel_id: ChannelId) {
 }
 
 pub async fn skip(ctx: &Context, guild_id: u64, channel_id: u64) -> bool {
-    next(&ctx, guild_id, channel_id).await
+    next(ctx, guild_id, channel_id).await
 }
 
 pub async fn stop(ctx: &Context, guild_id: u64) {
-    let manager = songbird::get(&ctx)
+    let stay_connected = settings::get(guild_id).await.stay_connected;
+
+    halt(ctx, guild_id, !stay_connected).await;
+}
+
+pub async fn leave(ctx: &Context, guild_id: u64) {
+    halt(ctx, guild_id, true).await;
+}
+
+async fn halt(ctx: &Context, guild_id: u64, leave: bool) {
+    let manager = songbird::get(ctx)
         .await
         .expect("Songbird Voice client placed in at initialisation.")
         .clone();
@@ -341,12 +921,76 @@ pub async fn stop(ctx: &Context, guild_id: u64) {
         return;
     }
 
+    retire_current(guild_id).await;
+
+    let prepared = PREPARED_TRACKS.lock().await.remove(&guild_id);
+
+    if let Some(prepared) = prepared {
+        supersede(&prepared.handle).await;
+    }
+
     let mut handler = handler_lock.as_ref().unwrap().lock().await;
 
     handler.stop();
-    handler.leave().await.unwrap();
+
+    if leave {
+        handler.leave().await.unwrap();
+
+        VOLUMES.lock().await.remove(&guild_id);
+    }
 
     playlist::reset(guild_id).await;
+}
+
+pub async fn join(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+) -> PlayerResult<SpeakerStatus> {
+    let manager = songbird::get(ctx)
+        .await
+        .expect("Songbird Voice client placed in at initialisation.")
+        .clone();
+
+    let (handler_lock, joined) = manager.join(guild_id, channel_id).await;
+
+    if joined.is_err() {
+        return Err(PlayerError::JoinFailed);
+    }
+
+    let _ = handler_lock.lock().await.deafen(true).await;
+
+    stage::become_speaker(ctx, channel_id).await
+}
 
-    CURRENT_TRACKS.lock().await.remove(&guild_id);
+pub async fn rejoin(ctx: &Context, guild_id: GuildId) {
+    let settings = settings::get(guild_id.0).await;
+
+    let channel_id = match settings.voice_channel_id {
+        Some(channel_id) if settings.stay_connected => ChannelId(channel_id),
+        _ => return,
+    };
+
+    if current_channel(ctx, guild_id).await.is_some() {
+        return;
+    }
+
+    let _ = join(ctx, guild_id, channel_id).await;
+}
+
+pub async fn current_channel(ctx: &Context, guild_id: GuildId) -> Option<ChannelId> {
+    let manager = songbird::get(ctx).await?;
+    let handler_lock = manager.get(guild_id)?;
+
+    let channel_id = handler_lock.lock().await.current_channel()?;
+
+    Some(ChannelId(channel_id.0))
+}
+
+pub fn member_channel(ctx: &Context, guild_id: GuildId, member: &Member) -> Option<ChannelId> {
+    guild_id
+        .to_guild_cached(&ctx.cache)?
+        .voice_states
+        .get(&member.user.id)
+        .and_then(|voice_state| voice_state.channel_id)
 }
@@ -1,4 +1,11 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
 
 use lazy_static::lazy_static;
 use serenity::{
@@ -9,25 +16,97 @@ use serenity::{
     },
     prelude::Context,
 };
-use songbird::input::Input;
+use songbird::input::{Input, Metadata};
 
-use super::player::{format_duration, MediaInfo};
+use super::player::{self, format_duration, MediaInfo};
+use super::query::SearchSource;
+use crate::settings;
 
 type PlaylistResult<T> = Result<T, PlaylistError>;
 
+// A fonte de uma entrada já preparada fica com a faixa pausada no player; se
+// essa faixa for descartada, a entrada é resolvida de novo quando for tocar.
 pub struct PlaylistItem {
+    pub id: u64,
     pub ctx: Context,
     pub uri: String,
     pub guild_id: GuildId,
     pub channel_id: ChannelId,
     pub member: Member,
+    pub source: Option<Input>,
+    pub metadata: Metadata,
+    pub origin: SearchSource,
+    round: u64,
+    pinned: bool,
+}
+
+impl PlaylistItem {
+    pub fn new(
+        ctx: Context,
+        uri: String,
+        guild_id: GuildId,
+        channel_id: ChannelId,
+        member: Member,
+        source: Input,
+        origin: SearchSource,
+    ) -> Self {
+        PlaylistItem {
+            id: ITEM_COUNT.fetch_add(1, Ordering::Relaxed),
+            ctx,
+            uri,
+            guild_id,
+            channel_id,
+            member,
+            metadata: *source.metadata.clone(),
+            source: Some(source),
+            origin,
+            round: 0,
+            pinned: false,
+        }
+    }
+}
+
+pub struct PreparedEntry {
+    pub id: u64,
+    pub ctx: Context,
+    pub guild_id: GuildId,
+    pub channel_id: ChannelId,
+    pub member: Member,
     pub source: Input,
+    pub origin: SearchSource,
+}
+
+#[derive(Default)]
+struct FairState {
+    current_round: u64,
+    user_rounds: HashMap<u64, u64>,
+}
+
+impl FairState {
+    // Cada pedido vai para a rodada seguinte ao último pedido da mesma pessoa.
+    fn assign(&mut self, user_id: u64) -> u64 {
+        let round = match self.user_rounds.get(&user_id) {
+            Some(user_round) => (user_round + 1).max(self.current_round),
+            None => self.current_round,
+        };
+
+        self.user_rounds.insert(user_id, round);
+
+        round
+    }
+
+    fn advance(&mut self, round: u64) {
+        self.current_round = self.current_round.max(round);
+    }
 }
 
 pub struct PlaylistInfo {
     pub limit_per_page: usize,
     pub total_pages: usize,
     pub total_tracks: usize,
+    pub total_duration: Duration,
+    pub remaining_duration: Option<Duration>,
+    pub has_live: bool,
     pub items: Vec<PlaylistItemInfo>,
 }
 
@@ -35,40 +114,125 @@ pub struct PlaylistInfo {
 pub struct PlaylistItemInfo {
     pub index: usize,
     pub media_info: MediaInfo,
+    pub requester: String,
+    pub starts_in: Option<Duration>,
 }
 
 unsafe impl Send for PlaylistItem {}
 
-const PLAYLIST_LIMIT: usize = 50;
+pub const PLAYLIST_LIMIT: usize = 50;
+pub const MAX_PLAYLIST_LIMIT: usize = 100;
+pub const PAGE_SIZE: usize = 3;
+pub const MAX_PAGE_SIZE: usize = 10;
 
 lazy_static! {
     static ref PLAYLISTS: Mutex<HashMap<u64, Vec<PlaylistItem>>> = Mutex::new(HashMap::new());
+    static ref FAIR_STATES: Mutex<HashMap<u64, FairState>> = Mutex::new(HashMap::new());
+    static ref ITEM_COUNT: AtomicU64 = AtomicU64::new(0);
 }
 
 #[derive(Debug)]
 pub enum PlaylistError {
     PlaylistFull,
     PlaylistEmpty,
+    UserQuotaReached(usize),
+    QueueTooLong(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Placement {
+    Back,
+    Front,
+    Top,
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct QueueLimits {
+    pub max_entries: usize,
+    pub max_entries_per_user: Option<usize>,
+    pub max_queue_duration: Option<Duration>,
+}
+
+impl Default for QueueLimits {
+    fn default() -> Self {
+        QueueLimits {
+            max_entries: PLAYLIST_LIMIT,
+            max_entries_per_user: None,
+            max_queue_duration: None,
+        }
+    }
 }
 
-pub async fn insert(guild_id: u64, item: PlaylistItem) -> PlaylistResult<()> {
+pub async fn insert(
+    guild_id: u64,
+    mut item: PlaylistItem,
+    limits: QueueLimits,
+    placement: Placement,
+) -> PlaylistResult<usize> {
+    let fair = settings::get(guild_id).await.fair_queue;
+
     prepare_playlist(guild_id).await;
 
     let mut guilds_playlist = PLAYLISTS.lock().await;
     let mut guild_playlist = guilds_playlist.get_mut(&guild_id).unwrap();
 
-    if guild_playlist.len() == PLAYLIST_LIMIT {
+    if guild_playlist.len() >= limits.max_entries {
         return Err(PlaylistError::PlaylistFull);
     }
 
-    guild_playlist.push(item);
+    if let Some(max_entries_per_user) = limits.max_entries_per_user {
+        let user_entries = guild_playlist
+            .iter()
+            .filter(|queued| queued.member.user.id == item.member.user.id)
+            .count();
+
+        if user_entries >= max_entries_per_user {
+            return Err(PlaylistError::UserQuotaReached(max_entries_per_user));
+        }
+    }
+
+    if let Some(max_queue_duration) = limits.max_queue_duration {
+        let queue_duration: Duration = guild_playlist
+            .iter()
+            .filter_map(|queued| queued.metadata.duration)
+            .sum();
+        let item_duration = item.metadata.duration.unwrap_or_default();
+
+        if queue_duration + item_duration > max_queue_duration {
+            return Err(PlaylistError::QueueTooLong(max_queue_duration));
+        }
+    }
+
+    let mut fair_states = FAIR_STATES.lock().await;
 
-    Ok(())
+    item.round = fair_states
+        .entry(guild_id)
+        .or_default()
+        .assign(item.member.user.id.0);
+    item.pinned = placement != Placement::Back;
+
+    let pinned = guild_playlist
+        .iter()
+        .map(|queued| queued.pinned)
+        .collect::<Vec<bool>>();
+    let index = insert_index(&pinned, placement);
+
+    guild_playlist.insert(index, item);
+
+    let position = play_order(guild_playlist, fair)
+        .iter()
+        .position(|i| *i == index)
+        .unwrap();
+
+    Ok(position)
 }
 
 pub async fn reset(guild_id: u64) -> Option<()> {
     let mut guilds_playlist = PLAYLISTS.lock().await;
 
+    FAIR_STATES.lock().await.remove(&guild_id);
+
     guilds_playlist.remove(&guild_id)?;
 
     Some(())
@@ -86,6 +250,39 @@ async fn prepare_playlist(guild_id: u64) {
 }
 
 pub async fn next(guild_id: u64) -> Option<PlaylistItem> {
+    take_first(guild_id, None).await
+}
+
+// Só tira a entrada se ela ainda for a próxima, para a faixa preparada não
+// tocar fora de ordem depois de um playnext ou de uma reordenação justa.
+pub async fn next_if(guild_id: u64, id: u64) -> Option<PlaylistItem> {
+    take_first(guild_id, Some(id)).await
+}
+
+// Mantém a entrada na fila, só leva a fonte para a faixa ser preparada.
+pub async fn prepare(guild_id: u64) -> Option<PreparedEntry> {
+    let fair = settings::get(guild_id).await.fair_queue;
+
+    let mut playlists = PLAYLISTS.lock().await;
+    let playlist = playlists.get_mut(&guild_id)?;
+
+    let index = *play_order(playlist, fair).first()?;
+    let item = &mut playlist[index];
+
+    Some(PreparedEntry {
+        id: item.id,
+        ctx: item.ctx.clone(),
+        guild_id: item.guild_id,
+        channel_id: item.channel_id,
+        member: item.member.clone(),
+        source: item.source.take()?,
+        origin: item.origin,
+    })
+}
+
+async fn take_first(guild_id: u64, id: Option<u64>) -> Option<PlaylistItem> {
+    let fair = settings::get(guild_id).await.fair_queue;
+
     let mut playlists = PLAYLISTS.lock().await;
 
     if playlists.is_empty() {
@@ -94,16 +291,89 @@ pub async fn next(guild_id: u64) -> Option<PlaylistItem> {
 
     let playlist = playlists.get_mut(&guild_id)?;
 
-    let item = playlist.remove(0);
+    let index = *play_order(playlist, fair).first()?;
+
+    if id.is_some_and(|id| playlist[index].id != id) {
+        return None;
+    }
+
+    let item = playlist.remove(index);
+
+    let mut fair_states = FAIR_STATES.lock().await;
 
-    if playlist.len() == 0 {
+    if playlist.is_empty() {
         playlists.remove(&guild_id).unwrap();
+        fair_states.remove(&guild_id);
+    } else if let Some(fair_state) = fair_states.get_mut(&guild_id) {
+        fair_state.advance(item.round);
     }
 
     Some(item)
 }
 
+// O playnext entra depois das músicas já adiantadas, na ordem em que foram
+// pedidas; o playtop e o playskip passam na frente de todas.
+fn insert_index(pinned: &[bool], placement: Placement) -> usize {
+    match placement {
+        Placement::Back => pinned.len(),
+        Placement::Front => pinned
+            .iter()
+            .rposition(|pinned| *pinned)
+            .map_or(0, |index| index + 1),
+        Placement::Top | Placement::Skip => 0,
+    }
+}
+
+// No modo justo as músicas tocam em rodadas, uma por pessoa, mantendo a ordem
+// em que cada uma pediu. As adiantadas furam a fila na mesma ordem dos dois
+// modos.
+fn play_order(guild_playlist: &[PlaylistItem], fair: bool) -> Vec<usize> {
+    let slots = guild_playlist
+        .iter()
+        .map(|item| (item.pinned, item.round))
+        .collect::<Vec<(bool, u64)>>();
+
+    slot_order(&slots, fair)
+}
+
+fn slot_order(slots: &[(bool, u64)], fair: bool) -> Vec<usize> {
+    let mut order = (0..slots.len()).collect::<Vec<usize>>();
+
+    if fair {
+        order.sort_by_key(|i| match slots[*i] {
+            (true, _) => (false, 0),
+            (false, round) => (true, round),
+        });
+    }
+
+    order
+}
+
+pub async fn len(guild_id: u64) -> usize {
+    match PLAYLISTS.lock().await.get(&guild_id) {
+        Some(guild_playlist) => guild_playlist.len(),
+        None => 0,
+    }
+}
+
+pub async fn entries(guild_id: u64) -> Vec<MediaInfo> {
+    let fair = settings::get(guild_id).await.fair_queue;
+
+    let guilds_playlist = PLAYLISTS.lock().await;
+
+    match guilds_playlist.get(&guild_id) {
+        Some(guild_playlist) => play_order(guild_playlist, fair)
+            .into_iter()
+            .map(|i| media_info(&guild_playlist[i]))
+            .collect(),
+        None => vec![],
+    }
+}
+
 pub async fn info(guild_id: u64, mut page: usize, limit: usize) -> Option<PlaylistInfo> {
+    let current_remaining = player::current_remaining(guild_id).await;
+    let fair = settings::get(guild_id).await.fair_queue;
+
     let mut guilds_playlist = PLAYLISTS.lock().await;
     let guild_playlist = guilds_playlist.get(&guild_id)?;
 
@@ -120,7 +390,7 @@ pub async fn info(guild_id: u64, mut page: usize, limit: usize) -> Option<Playli
         return None;
     }
 
-    if guild_playlist.len() == 0 {
+    if guild_playlist.is_empty() {
         return None;
     }
 
@@ -132,35 +402,138 @@ pub async fn info(guild_id: u64, mut page: usize, limit: usize) -> Option<Playli
         max_length = guild_playlist.len();
     }
 
-    for (i, item) in guild_playlist[offset..max_length].iter().enumerate() {
-        let metadata = item.source.metadata.clone();
-
-        let title = metadata.title.unwrap();
-        let thumb = metadata.thumbnail.unwrap();
-        let artist = metadata.artist.unwrap();
-        let video_duration = metadata.duration;
-        let url = metadata.source_url.unwrap();
-        let duration = format_duration(metadata.duration);
-
-        items.push(PlaylistItemInfo {
-            index: offset + i,
-            media_info: MediaInfo {
-                title,
-                thumb,
-                artist,
-                video_duration,
-                url,
-                duration,
-            },
-        })
+    let mut starts_in = current_remaining;
+    let mut total_duration = Duration::ZERO;
+    let mut has_live = current_remaining.is_none();
+
+    for (i, index) in play_order(guild_playlist, fair).into_iter().enumerate() {
+        let item = &guild_playlist[index];
+        let duration = item.metadata.duration;
+
+        if i >= offset && i < max_length {
+            items.push(PlaylistItemInfo {
+                index: i,
+                media_info: media_info(item),
+                requester: item.member.display_name().to_string(),
+                starts_in,
+            })
+        }
+
+        total_duration += duration.unwrap_or_default();
+        has_live |= duration.is_none();
+        starts_in = starts_in
+            .zip(duration)
+            .map(|(start, duration)| start + duration);
     }
 
+    let remaining_duration = current_remaining
+        .filter(|_| !has_live)
+        .map(|current_remaining| current_remaining + total_duration);
+
     let mut info = PlaylistInfo {
         items,
         limit_per_page: limit,
-        total_pages: guild_playlist.len() / limit + 1,
+        total_pages: guild_playlist.len().div_ceil(limit),
         total_tracks: guild_playlist.len(),
+        total_duration,
+        remaining_duration,
+        has_live,
     };
 
-    return Some(info);
+    Some(info)
+}
+
+fn media_info(item: &PlaylistItem) -> MediaInfo {
+    let metadata = item.metadata.clone();
+
+    let title = metadata.title.unwrap_or("???".to_string());
+    let thumb = metadata.thumbnail.unwrap_or("???".to_string());
+    let artist = metadata
+        .artist
+        .or(metadata.channel)
+        .unwrap_or("???".to_string());
+    let video_duration = metadata.duration;
+    let url = metadata.source_url.unwrap_or(item.uri.clone());
+    let duration = format_duration(metadata.duration);
+
+    MediaInfo {
+        title,
+        thumb,
+        artist,
+        video_duration,
+        url,
+        duration,
+        origin: item.origin,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn play_order_keeps_insertion_order_without_fair_mode() {
+        let slots = [(false, 2), (true, 0), (false, 0)];
+
+        assert_eq!(slot_order(&slots, false), vec![0, 1, 2]);
+    }
+
+    #[test]
+    fn play_order_plays_one_track_per_user_each_round() {
+        let mut fair_state = FairState::default();
+
+        // A pede três músicas seguidas e B pede uma depois.
+        let slots = [1, 1, 1, 2]
+            .into_iter()
+            .map(|user_id| (false, fair_state.assign(user_id)))
+            .collect::<Vec<(bool, u64)>>();
+
+        assert_eq!(slot_order(&slots, true), vec![0, 3, 1, 2]);
+    }
+
+    #[test]
+    fn play_order_puts_pinned_tracks_first() {
+        let slots = [(true, 3), (true, 0), (false, 1), (false, 0)];
+
+        assert_eq!(slot_order(&slots, false), vec![0, 1, 2, 3]);
+        assert_eq!(slot_order(&slots, true), vec![0, 1, 3, 2]);
+    }
+
+    #[test]
+    fn insert_index_keeps_playnext_in_request_order() {
+        let mut pinned = vec![false, false];
+
+        for _ in 0..2 {
+            let index = insert_index(&pinned, Placement::Front);
+
+            pinned.insert(index, true);
+        }
+
+        assert_eq!(pinned, vec![true, true, false, false]);
+        assert_eq!(insert_index(&pinned, Placement::Front), 2);
+        assert_eq!(insert_index(&pinned, Placement::Back), 4);
+    }
+
+    #[test]
+    fn insert_index_puts_playtop_and_playskip_ahead_of_pinned_tracks() {
+        let pinned = [true, true, false];
+
+        assert_eq!(insert_index(&pinned, Placement::Top), 0);
+        assert_eq!(insert_index(&pinned, Placement::Skip), 0);
+        assert_eq!(insert_index(&[], Placement::Front), 0);
+    }
+
+    #[test]
+    fn fair_state_starts_new_users_in_the_current_round() {
+        let mut fair_state = FairState::default();
+
+        assert_eq!(fair_state.assign(1), 0);
+        assert_eq!(fair_state.assign(1), 1);
+
+        fair_state.advance(1);
+        fair_state.advance(0);
+
+        assert_eq!(fair_state.assign(2), 1);
+        assert_eq!(fair_state.assign(1), 2);
+    }
 }
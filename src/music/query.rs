@@ -1,7 +1,14 @@
 use std::process::{Command, Stdio};
 
+use reqwest::Url;
 use serde::de::Error;
-use songbird::input::Input;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use serenity::prelude::TypeMapKey;
+use songbird::input::{children_to_reader, Codec, Container, Input, Metadata};
+use youtube_dl::{YoutubeDl, YoutubeDlOutput};
+
+use super::cache;
 
 type QueryResult<T> = Result<T, QueryError>;
 
@@ -10,18 +17,427 @@ pub enum QueryError {
     NotFound,
 }
 
-pub async fn query_video(uri: String) -> QueryResult<Input> {
-    let uri = if !uri.starts_with("http") {
-        format!("ytsearch1:{}", uri)
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchSource {
+    #[default]
+    YouTube,
+    YouTubeMusic,
+    SoundCloud,
+    Bandcamp,
+    Link,
+}
+
+impl TypeMapKey for SearchSource {
+    type Value = SearchSource;
+}
+
+impl SearchSource {
+    pub fn from_prefix(prefix: &str) -> Option<Self> {
+        match prefix.to_lowercase().as_str() {
+            "yt" => Some(SearchSource::YouTube),
+            "ytm" => Some(SearchSource::YouTubeMusic),
+            "sc" => Some(SearchSource::SoundCloud),
+            "bc" => Some(SearchSource::Bandcamp),
+            _ => None,
+        }
+    }
+
+    pub fn from_url(url: &str) -> Self {
+        let host = Url::parse(url)
+            .ok()
+            .and_then(|url| url.host_str().map(|host| host.to_lowercase()))
+            .unwrap_or_default();
+
+        if host == "music.youtube.com" {
+            return SearchSource::YouTubeMusic;
+        }
+
+        if host.ends_with("youtube.com") || host == "youtu.be" {
+            return SearchSource::YouTube;
+        }
+
+        if host.ends_with("soundcloud.com") {
+            return SearchSource::SoundCloud;
+        }
+
+        if host.ends_with("bandcamp.com") {
+            return SearchSource::Bandcamp;
+        }
+
+        SearchSource::Link
+    }
+
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            SearchSource::YouTube => "yt",
+            SearchSource::YouTubeMusic => "ytm",
+            SearchSource::SoundCloud => "sc",
+            SearchSource::Bandcamp => "bc",
+            SearchSource::Link => "",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SearchSource::YouTube => "YouTube",
+            SearchSource::YouTubeMusic => "YouTube Music",
+            SearchSource::SoundCloud => "SoundCloud",
+            SearchSource::Bandcamp => "Bandcamp",
+            SearchSource::Link => "Link",
+        }
+    }
+}
+
+pub fn parse_query(query: &str, default_source: SearchSource) -> (SearchSource, String) {
+    let query = query.trim();
+
+    if query.starts_with("http") {
+        return (SearchSource::from_url(query), query.to_string());
+    }
+
+    if let Some((prefix, terms)) = query.split_once(':') {
+        if let Some(source) = SearchSource::from_prefix(prefix.trim()) {
+            return (source, terms.trim().to_string());
+        }
+    }
+
+    (default_source, query.to_string())
+}
+
+pub async fn query_video(
+    uri: String,
+    default_source: SearchSource,
+) -> QueryResult<(Input, SearchSource)> {
+    let (source, terms) = parse_query(&uri, default_source);
+
+    if terms.is_empty() {
+        return Err(QueryError::NotFound);
+    }
+
+    let key = if terms.starts_with("http") {
+        cache::url_key(&terms)
     } else {
-        uri
+        cache::search_key(source, &terms)
     };
 
-    let source = songbird::ytdl(uri).await;
+    let cached = cache::get(&key).await;
+
+    // Com o stream ainda válido a música toca sem rodar o yt-dlp de novo.
+    if let Some(media) = &cached {
+        if let Some(input) = media
+            .valid_stream()
+            .and_then(|stream_url| stream_input(stream_url, media.metadata()))
+        {
+            return Ok((input, media.origin));
+        }
+    }
+
+    // Sem stream válido, a página já conhecida pelo menos evita repetir a busca.
+    let (uri, source) = match cached {
+        Some(media) => (media.url, media.origin),
+        None => {
+            let uri = match source {
+                SearchSource::Link => terms,
+                _ if terms.starts_with("http") => terms,
+                SearchSource::YouTube => format!("ytsearch1:{}", terms),
+                SearchSource::SoundCloud => format!("scsearch1:{}", terms),
+                SearchSource::YouTubeMusic => search_youtube_music(terms).await?,
+                SearchSource::Bandcamp => search_bandcamp(terms).await?,
+            };
+
+            (uri, source)
+        }
+    };
 
-    if let Err(_) = source {
+    let (stream_url, metadata) = resolve(uri).await?;
+
+    let input = stream_input(&stream_url, metadata.clone()).ok_or(QueryError::NotFound)?;
+
+    if let Some(media) = cache::CachedMedia::from_metadata(&metadata, source, &stream_url) {
+        let url_key = cache::url_key(&media.url);
+        let keys = if key == url_key {
+            vec![key]
+        } else {
+            vec![key, url_key]
+        };
+
+        cache::store(&keys, media).await;
+    }
+
+    Ok((input, source))
+}
+
+// Mesmos formatos do `songbird::ytdl`, mas o yt-dlp só resolve o stream e o
+// ffmpeg baixa direto, o que permite reaproveitar o stream em cache.
+async fn resolve(uri: String) -> QueryResult<(String, Metadata)> {
+    resolve_with(tokio::process::Command::new("yt-dlp"), uri).await
+}
+
+async fn resolve_with(
+    mut ytdlp: tokio::process::Command,
+    uri: String,
+) -> QueryResult<(String, Metadata)> {
+    let output = ytdlp
+        .args([
+            "-j",
+            "-f",
+            "webm[abr>0]/bestaudio/best",
+            "--no-playlist",
+            "--ignore-config",
+            "--no-warnings",
+            "--",
+        ])
+        .arg(uri)
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .await
+        .map_err(|_| QueryError::NotFound)?;
+
+    if !output.status.success() {
         return Err(QueryError::NotFound);
     }
 
-    return Ok(source.unwrap());
+    let line = output
+        .stdout
+        .split(|byte| *byte == b'\n')
+        .next()
+        .ok_or(QueryError::NotFound)?;
+
+    let value: Value = serde_json::from_slice(line).map_err(|_| QueryError::NotFound)?;
+
+    let stream_url = value
+        .get("url")
+        .and_then(Value::as_str)
+        .ok_or(QueryError::NotFound)?
+        .to_string();
+
+    Ok((stream_url, Metadata::from_ytdl_output(value)))
+}
+
+fn stream_input(stream_url: &str, metadata: Metadata) -> Option<Input> {
+    let ffmpeg = Command::new("ffmpeg")
+        .args([
+            "-reconnect",
+            "1",
+            "-reconnect_streamed",
+            "1",
+            "-reconnect_delay_max",
+            "5",
+            "-i",
+        ])
+        .arg(stream_url)
+        .args([
+            "-f",
+            "s16le",
+            "-ac",
+            "2",
+            "-ar",
+            "48000",
+            "-acodec",
+            "pcm_f32le",
+            "-",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .stdout(Stdio::piped())
+        .spawn()
+        .ok()?;
+
+    Some(Input::new(
+        true,
+        children_to_reader::<f32>(vec![ffmpeg]),
+        Codec::FloatPcm,
+        Container::Raw,
+        Some(metadata),
+    ))
+}
+
+// O yt-dlp não tem uma chave de busca para o YouTube Music, então a página de
+// busca é lida como playlist e o primeiro resultado é usado.
+async fn search_youtube_music(terms: String) -> QueryResult<String> {
+    let mut search_url =
+        Url::parse_with_params("https://music.youtube.com/search", &[("q", terms)])
+            .map_err(|_| QueryError::NotFound)?;
+
+    search_url.set_fragment(Some("songs"));
+
+    let output = tokio::task::spawn_blocking(move || {
+        YoutubeDl::new(search_url.as_str())
+            .youtube_dl_path("yt-dlp")
+            .flat_playlist(true)
+            .extra_arg("--playlist-items")
+            .extra_arg("1")
+            .run()
+    })
+    .await
+    .map_err(|_| QueryError::NotFound)?
+    .map_err(|_| QueryError::NotFound)?;
+
+    let entry = match output {
+        YoutubeDlOutput::Playlist(playlist) => playlist
+            .entries
+            .and_then(|entries| entries.into_iter().next())
+            .ok_or(QueryError::NotFound)?,
+        YoutubeDlOutput::SingleVideo(video) => *video,
+    };
+
+    Ok(entry
+        .webpage_url
+        .or(entry.url)
+        .unwrap_or(format!("https://music.youtube.com/watch?v={}", entry.id)))
+}
+
+// O Bandcamp também não possui busca no yt-dlp, então a primeira faixa é lida
+// diretamente do HTML da página de busca.
+async fn search_bandcamp(terms: String) -> QueryResult<String> {
+    let search_url = Url::parse_with_params(
+        "https://bandcamp.com/search",
+        &[("q", terms.as_str()), ("item_type", "t")],
+    )
+    .map_err(|_| QueryError::NotFound)?;
+
+    let html = reqwest::get(search_url)
+        .await
+        .map_err(|_| QueryError::NotFound)?
+        .text()
+        .await
+        .map_err(|_| QueryError::NotFound)?;
+
+    let item_url = html
+        .split("class=\"itemurl\"")
+        .nth(1)
+        .and_then(|item| item.split("href=\"").nth(1))
+        .and_then(|href| href.split('"').next())
+        .ok_or(QueryError::NotFound)?;
+
+    let track_url = item_url.split('?').next().unwrap_or(item_url);
+
+    Ok(track_url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_query_uses_prefix() {
+        assert_eq!(
+            parse_query("sc: lo-fi beats ", SearchSource::YouTube),
+            (SearchSource::SoundCloud, "lo-fi beats".to_string())
+        );
+        assert_eq!(
+            parse_query("YTM:song", SearchSource::YouTube),
+            (SearchSource::YouTubeMusic, "song".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_query_falls_back_to_default_source() {
+        assert_eq!(
+            parse_query("  hello world  ", SearchSource::Bandcamp),
+            (SearchSource::Bandcamp, "hello world".to_string())
+        );
+        assert_eq!(
+            parse_query("artist: song", SearchSource::YouTube),
+            (SearchSource::YouTube, "artist: song".to_string())
+        );
+    }
+
+    #[test]
+    fn parse_query_detects_links() {
+        assert_eq!(
+            parse_query("https://youtu.be/abc", SearchSource::SoundCloud),
+            (SearchSource::YouTube, "https://youtu.be/abc".to_string())
+        );
+    }
+
+    static MOCKS: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+    // Roda o script pelo `sh` no lugar do yt-dlp, com os mesmos argumentos.
+    async fn resolve_mock(script: &str, uri: &str) -> QueryResult<(String, Metadata)> {
+        let path = std::env::temp_dir().join(format!(
+            "ytdlp-mock-{}-{}.sh",
+            std::process::id(),
+            MOCKS.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::write(&path, script).unwrap();
+
+        let mut ytdlp = tokio::process::Command::new("sh");
+        ytdlp.arg(&path);
+
+        let result = resolve_with(ytdlp, uri.to_string()).await;
+        let _ = std::fs::remove_file(&path);
+
+        result
+    }
+
+    #[tokio::test]
+    async fn resolve_reads_stream_and_metadata() {
+        let script = r#"for arg; do uri="$arg"; done
+printf '{"url":"https://cdn.example/a.webm","title":"Song","channel":"Artist","upload_date":"20240131","duration":90,"webpage_url":"%s"}\n{"url":"ignored"}\n' "$uri"
+"#;
+
+        let (stream_url, metadata) = resolve_mock(script, "https://youtu.be/a").await.unwrap();
+
+        assert_eq!(stream_url, "https://cdn.example/a.webm");
+        assert_eq!(metadata.title.as_deref(), Some("Song"));
+        assert_eq!(metadata.channel.as_deref(), Some("Artist"));
+        assert_eq!(metadata.date.as_deref(), Some("20240131"));
+        assert_eq!(metadata.source_url.as_deref(), Some("https://youtu.be/a"));
+        assert_eq!(
+            super::super::player::format_date(metadata.date.as_deref()),
+            "31/01/2024"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_accepts_output_without_date() {
+        let script = r#"echo '{"url":"https://cdn.example/b.mp3","title":"Track","webpage_url":"https://artist.bandcamp.com/track/b"}'
+"#;
+
+        let (_, metadata) = resolve_mock(script, "https://artist.bandcamp.com/track/b")
+            .await
+            .unwrap();
+
+        assert_eq!(metadata.date, None);
+        assert_eq!(
+            super::super::player::format_date(metadata.date.as_deref()),
+            "Desconhecida"
+        );
+    }
+
+    #[tokio::test]
+    async fn resolve_fails_without_stream() {
+        let failed = resolve_mock("exit 1\n", "ytsearch1:a").await;
+        let no_url = resolve_mock("echo '{\"title\":\"a\"}'\n", "ytsearch1:ab").await;
+        let not_json = resolve_mock("echo 'ERROR'\n", "ytsearch1:abc").await;
+
+        assert!(matches!(failed, Err(QueryError::NotFound)));
+        assert!(matches!(no_url, Err(QueryError::NotFound)));
+        assert!(matches!(not_json, Err(QueryError::NotFound)));
+    }
+
+    #[test]
+    fn from_url_matches_hosts() {
+        let cases = [
+            (
+                "https://music.youtube.com/watch?v=a",
+                SearchSource::YouTubeMusic,
+            ),
+            ("https://www.youtube.com/watch?v=a", SearchSource::YouTube),
+            ("https://youtu.be/a", SearchSource::YouTube),
+            ("https://m.soundcloud.com/a/b", SearchSource::SoundCloud),
+            (
+                "https://artist.bandcamp.com/track/a",
+                SearchSource::Bandcamp,
+            ),
+            ("https://example.com/a.mp3", SearchSource::Link),
+            ("not a url", SearchSource::Link),
+        ];
+
+        for (url, source) in cases {
+            assert_eq!(SearchSource::from_url(url), source, "{url}");
+        }
+    }
 }
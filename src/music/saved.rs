@@ -0,0 +1,279 @@
+use chrono::Utc;
+use redis::{aio::Connection, AsyncCommands, RedisResult};
+use serde::{Deserialize, Serialize};
+
+use super::player::MediaInfo;
+use super::playlist::MAX_PLAYLIST_LIMIT;
+use crate::database;
+
+type SavedResult<T> = Result<T, SavedError>;
+
+pub const MAX_USER_PLAYLISTS: usize = 10;
+pub const MAX_GUILD_PLAYLISTS: usize = 50;
+pub const MAX_PLAYLIST_TRACKS: usize = MAX_PLAYLIST_LIMIT + 1;
+const MAX_NAME_LENGTH: usize = 32;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedTrack {
+    pub title: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedPlaylist {
+    pub name: String,
+    pub owner_id: u64,
+    pub tracks: Vec<SavedTrack>,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SavedScope {
+    User,
+    Guild,
+}
+
+#[derive(Debug)]
+pub enum SavedError {
+    InvalidName,
+    Empty,
+    TooManyTracks,
+    LimitReached,
+    AlreadyExists,
+    NotFound,
+    NotOwner,
+    Database,
+}
+
+impl From<redis::RedisError> for SavedError {
+    fn from(_: redis::RedisError) -> Self {
+        SavedError::Database
+    }
+}
+
+impl From<&MediaInfo> for SavedTrack {
+    fn from(media_info: &MediaInfo) -> Self {
+        SavedTrack {
+            title: media_info.title.clone(),
+            url: media_info.url.clone(),
+        }
+    }
+}
+
+pub async fn save(user_id: u64, name: &str, tracks: Vec<SavedTrack>) -> SavedResult<()> {
+    let field = field_name(name)?;
+
+    if tracks.is_empty() {
+        return Err(SavedError::Empty);
+    }
+
+    if tracks.len() > MAX_PLAYLIST_TRACKS {
+        return Err(SavedError::TooManyTracks);
+    }
+
+    let mut connection = database::connection().await?;
+    let key = scope_key(SavedScope::User, 0, user_id);
+
+    let exists: bool = connection.hexists(&key, &field).await?;
+    let count: usize = connection.hlen(&key).await?;
+
+    if !exists && count >= MAX_USER_PLAYLISTS {
+        return Err(SavedError::LimitReached);
+    }
+
+    let playlist = SavedPlaylist {
+        name: name.trim().to_string(),
+        owner_id: user_id,
+        tracks,
+        created_at: Utc::now().timestamp(),
+    };
+
+    write(&mut connection, &key, &field, &playlist).await?;
+
+    Ok(())
+}
+
+pub async fn find(
+    guild_id: u64,
+    user_id: u64,
+    name: &str,
+) -> SavedResult<(SavedScope, SavedPlaylist)> {
+    let field = field_name(name)?;
+    let mut connection = database::connection().await?;
+
+    for scope in [SavedScope::User, SavedScope::Guild] {
+        let key = scope_key(scope, guild_id, user_id);
+
+        if let Some(playlist) = read(&mut connection, &key, &field).await? {
+            return Ok((scope, playlist));
+        }
+    }
+
+    Err(SavedError::NotFound)
+}
+
+pub async fn list(
+    guild_id: u64,
+    user_id: u64,
+) -> SavedResult<(Vec<SavedPlaylist>, Vec<SavedPlaylist>)> {
+    let mut connection = database::connection().await?;
+
+    let own = read_all(
+        &mut connection,
+        &scope_key(SavedScope::User, guild_id, user_id),
+    )
+    .await?;
+    let shared = read_all(
+        &mut connection,
+        &scope_key(SavedScope::Guild, guild_id, user_id),
+    )
+    .await?;
+
+    Ok((own, shared))
+}
+
+pub async fn delete(
+    guild_id: u64,
+    user_id: u64,
+    name: &str,
+    is_manager: bool,
+) -> SavedResult<SavedScope> {
+    let field = field_name(name)?;
+    let (scope, playlist) = find(guild_id, user_id, name).await?;
+
+    check_owner(scope, &playlist, user_id, is_manager)?;
+
+    let mut connection = database::connection().await?;
+
+    let _: () = connection
+        .hdel(scope_key(scope, guild_id, user_id), &field)
+        .await?;
+
+    Ok(scope)
+}
+
+pub async fn rename(
+    guild_id: u64,
+    user_id: u64,
+    name: &str,
+    new_name: &str,
+    is_manager: bool,
+) -> SavedResult<SavedScope> {
+    let field = field_name(name)?;
+    let new_field = field_name(new_name)?;
+    let (scope, mut playlist) = find(guild_id, user_id, name).await?;
+
+    check_owner(scope, &playlist, user_id, is_manager)?;
+
+    let mut connection = database::connection().await?;
+    let key = scope_key(scope, guild_id, user_id);
+
+    let taken: bool = connection.hexists(&key, &new_field).await?;
+
+    if new_field != field && taken {
+        return Err(SavedError::AlreadyExists);
+    }
+
+    playlist.name = new_name.trim().to_string();
+
+    let _: () = connection.hdel(&key, &field).await?;
+
+    write(&mut connection, &key, &new_field, &playlist).await?;
+
+    Ok(scope)
+}
+
+pub async fn share(guild_id: u64, user_id: u64, name: &str) -> SavedResult<()> {
+    let field = field_name(name)?;
+    let mut connection = database::connection().await?;
+
+    let playlist = read(
+        &mut connection,
+        &scope_key(SavedScope::User, guild_id, user_id),
+        &field,
+    )
+    .await?
+    .ok_or(SavedError::NotFound)?;
+
+    let key = scope_key(SavedScope::Guild, guild_id, user_id);
+
+    match read(&mut connection, &key, &field).await? {
+        Some(shared) if shared.owner_id != user_id => return Err(SavedError::AlreadyExists),
+        Some(_) => {}
+        None => {
+            let count: usize = connection.hlen(&key).await?;
+
+            if count >= MAX_GUILD_PLAYLISTS {
+                return Err(SavedError::LimitReached);
+            }
+        }
+    }
+
+    write(&mut connection, &key, &field, &playlist).await?;
+
+    Ok(())
+}
+
+fn check_owner(
+    scope: SavedScope,
+    playlist: &SavedPlaylist,
+    user_id: u64,
+    is_manager: bool,
+) -> SavedResult<()> {
+    if scope == SavedScope::Guild && playlist.owner_id != user_id && !is_manager {
+        return Err(SavedError::NotOwner);
+    }
+
+    Ok(())
+}
+
+fn field_name(name: &str) -> SavedResult<String> {
+    let name = name.trim();
+
+    if name.is_empty() || name.chars().count() > MAX_NAME_LENGTH {
+        return Err(SavedError::InvalidName);
+    }
+
+    Ok(name.to_lowercase())
+}
+
+fn scope_key(scope: SavedScope, guild_id: u64, user_id: u64) -> String {
+    match scope {
+        SavedScope::User => database::user_key(user_id, "playlists"),
+        SavedScope::Guild => database::guild_key(guild_id, "playlists"),
+    }
+}
+
+async fn read(
+    connection: &mut Connection,
+    key: &str,
+    field: &str,
+) -> RedisResult<Option<SavedPlaylist>> {
+    let value: Option<String> = connection.hget(key, field).await?;
+
+    Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+}
+
+async fn read_all(connection: &mut Connection, key: &str) -> RedisResult<Vec<SavedPlaylist>> {
+    let values: Vec<String> = connection.hvals(key).await?;
+
+    let mut playlists = values
+        .iter()
+        .filter_map(|value| serde_json::from_str::<SavedPlaylist>(value).ok())
+        .collect::<Vec<SavedPlaylist>>();
+
+    playlists.sort_by_key(|playlist| playlist.name.to_lowercase());
+
+    Ok(playlists)
+}
+
+async fn write(
+    connection: &mut Connection,
+    key: &str,
+    field: &str,
+    playlist: &SavedPlaylist,
+) -> RedisResult<()> {
+    let value = serde_json::to_string(playlist).unwrap();
+
+    connection.hset(key, field, value).await
+}
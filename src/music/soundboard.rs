@@ -0,0 +1,274 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use redis::{aio::Connection, AsyncCommands, RedisResult};
+use serde::{Deserialize, Serialize};
+use serenity::{model::channel::Attachment, prelude::Context};
+use songbird::create_player;
+use tokio::sync::Mutex;
+
+use crate::database;
+
+type SoundboardResult<T> = Result<T, SoundboardError>;
+
+pub const MAX_CLIP_LENGTH: Duration = Duration::from_secs(10);
+pub const MAX_CLIP_SIZE: u64 = 2 * 1024 * 1024;
+pub const MAX_CLIPS: usize = 50;
+pub const SFX_COOLDOWN: Duration = Duration::from_secs(5);
+const MAX_NAME_LENGTH: usize = 32;
+const CLIPS_DIR: &str = "data/soundboard";
+const CLIP_EXTENSIONS: [&str; 6] = ["mp3", "ogg", "opus", "wav", "m4a", "webm"];
+const RESERVED_NAMES: [&str; 4] = ["add", "remove", "list", "volume"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoundClip {
+    pub name: String,
+    pub file: String,
+    pub duration_ms: u64,
+    pub volume: f32,
+    pub uploader_id: u64,
+}
+
+#[derive(Debug)]
+pub enum SoundboardError {
+    InvalidName,
+    InvalidFile,
+    TooLarge,
+    TooLong,
+    LimitReached,
+    AlreadyExists,
+    NotFound,
+    Cooldown(Duration),
+    NotConnected,
+    Storage,
+    Database,
+}
+
+impl From<redis::RedisError> for SoundboardError {
+    fn from(_: redis::RedisError) -> Self {
+        SoundboardError::Database
+    }
+}
+
+impl From<std::io::Error> for SoundboardError {
+    fn from(_: std::io::Error) -> Self {
+        SoundboardError::Storage
+    }
+}
+
+lazy_static! {
+    static ref COOLDOWNS: Mutex<HashMap<u64, Instant>> = Mutex::new(HashMap::new());
+}
+
+pub async fn add(
+    guild_id: u64,
+    uploader_id: u64,
+    name: &str,
+    attachment: &Attachment,
+    volume: f32,
+) -> SoundboardResult<SoundClip> {
+    let field = field_name(name)?;
+
+    let extension = attachment
+        .filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_lowercase())
+        .filter(|extension| CLIP_EXTENSIONS.contains(&extension.as_str()))
+        .ok_or(SoundboardError::InvalidFile)?;
+
+    if attachment.size > MAX_CLIP_SIZE {
+        return Err(SoundboardError::TooLarge);
+    }
+
+    let mut connection = database::connection().await?;
+    let key = soundboard_key(guild_id);
+
+    let exists: bool = connection.hexists(&key, &field).await?;
+    let count: usize = connection.hlen(&key).await?;
+
+    if exists {
+        return Err(SoundboardError::AlreadyExists);
+    }
+
+    if count >= MAX_CLIPS {
+        return Err(SoundboardError::LimitReached);
+    }
+
+    let bytes = attachment
+        .download()
+        .await
+        .map_err(|_| SoundboardError::InvalidFile)?;
+
+    let file = format!("{field}.{extension}");
+    let path = clip_path(guild_id, &file);
+
+    tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+    tokio::fs::write(&path, bytes).await?;
+
+    let duration = match songbird::ffmpeg(&path).await {
+        Ok(source) => source.metadata.duration,
+        Err(_) => {
+            let _ = tokio::fs::remove_file(&path).await;
+
+            return Err(SoundboardError::InvalidFile);
+        }
+    };
+
+    let duration = match duration {
+        Some(duration) if duration <= MAX_CLIP_LENGTH => duration,
+        _ => {
+            let _ = tokio::fs::remove_file(&path).await;
+
+            return Err(SoundboardError::TooLong);
+        }
+    };
+
+    let clip = SoundClip {
+        name: field.clone(),
+        file,
+        duration_ms: duration.as_millis() as u64,
+        volume,
+        uploader_id,
+    };
+
+    write(&mut connection, &key, &clip).await?;
+
+    Ok(clip)
+}
+
+pub async fn remove(guild_id: u64, name: &str) -> SoundboardResult<()> {
+    let field = field_name(name)?;
+    let mut connection = database::connection().await?;
+    let key = soundboard_key(guild_id);
+
+    let clip = read(&mut connection, &key, &field)
+        .await?
+        .ok_or(SoundboardError::NotFound)?;
+
+    let _: () = connection.hdel(&key, &field).await?;
+    let _ = tokio::fs::remove_file(clip_path(guild_id, &clip.file)).await;
+
+    Ok(())
+}
+
+pub async fn set_volume(guild_id: u64, name: &str, volume: f32) -> SoundboardResult<()> {
+    let field = field_name(name)?;
+    let mut connection = database::connection().await?;
+    let key = soundboard_key(guild_id);
+
+    let mut clip = read(&mut connection, &key, &field)
+        .await?
+        .ok_or(SoundboardError::NotFound)?;
+
+    clip.volume = volume;
+
+    write(&mut connection, &key, &clip).await?;
+
+    Ok(())
+}
+
+pub async fn list(guild_id: u64) -> SoundboardResult<Vec<SoundClip>> {
+    let mut connection = database::connection().await?;
+
+    let values: Vec<String> = connection.hvals(soundboard_key(guild_id)).await?;
+
+    let mut clips = values
+        .iter()
+        .filter_map(|value| serde_json::from_str::<SoundClip>(value).ok())
+        .collect::<Vec<SoundClip>>();
+
+    clips.sort_by(|a, b| a.name.cmp(&b.name));
+
+    Ok(clips)
+}
+
+pub async fn play(ctx: &Context, guild_id: u64, name: &str) -> SoundboardResult<()> {
+    let field = field_name(name)?;
+
+    if let Some(last_played) = COOLDOWNS.lock().await.get(&guild_id) {
+        let elapsed = last_played.elapsed();
+
+        if elapsed < SFX_COOLDOWN {
+            return Err(SoundboardError::Cooldown(SFX_COOLDOWN - elapsed));
+        }
+    }
+
+    let mut connection = database::connection().await?;
+
+    let clip = read(&mut connection, &soundboard_key(guild_id), &field)
+        .await?
+        .ok_or(SoundboardError::NotFound)?;
+
+    let manager = songbird::get(ctx).await.unwrap();
+
+    let handler_lock = manager.get(guild_id).ok_or(SoundboardError::NotConnected)?;
+
+    let source = songbird::ffmpeg(clip_path(guild_id, &clip.file))
+        .await
+        .map_err(|_| SoundboardError::Storage)?;
+
+    let mut handler = handler_lock.lock().await;
+
+    if handler.current_connection().is_none() {
+        return Err(SoundboardError::NotConnected);
+    }
+
+    let (mut track, _) = create_player(source);
+
+    track.set_volume(clip.volume);
+    handler.play(track);
+
+    COOLDOWNS.lock().await.insert(guild_id, Instant::now());
+
+    Ok(())
+}
+
+fn field_name(name: &str) -> SoundboardResult<String> {
+    let name = name.trim().to_lowercase();
+
+    let valid_chars = name
+        .chars()
+        .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+
+    if name.is_empty()
+        || name.len() > MAX_NAME_LENGTH
+        || !valid_chars
+        || RESERVED_NAMES.contains(&name.as_str())
+    {
+        return Err(SoundboardError::InvalidName);
+    }
+
+    Ok(name)
+}
+
+fn soundboard_key(guild_id: u64) -> String {
+    database::guild_key(guild_id, "soundboard")
+}
+
+fn clip_path(guild_id: u64, file: &str) -> PathBuf {
+    PathBuf::from(CLIPS_DIR)
+        .join(guild_id.to_string())
+        .join(file)
+}
+
+async fn read(
+    connection: &mut Connection,
+    key: &str,
+    field: &str,
+) -> RedisResult<Option<SoundClip>> {
+    let value: Option<String> = connection.hget(key, field).await?;
+
+    Ok(value.and_then(|value| serde_json::from_str(&value).ok()))
+}
+
+async fn write(connection: &mut Connection, key: &str, clip: &SoundClip) -> RedisResult<()> {
+    let value = serde_json::to_string(clip).unwrap();
+
+    let _: () = connection.hset(key, &clip.name, value).await?;
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use lazy_static::lazy_static;
+use serenity::prelude::Context;
+use songbird::{create_player, Event, TrackEvent};
+use tokio::{io::AsyncWriteExt, process::Command, sync::Mutex};
+
+use super::handler::SpeechEndHandle;
+use super::player;
+
+type SpeechResult<T> = Result<T, SpeechError>;
+
+pub const MAX_SPEECH_LENGTH: usize = 200;
+const TTS_COMMAND: &str = "espeak-ng";
+const TTS_VOICE: &str = "pt-br";
+pub const DUCK_VOLUME: f32 = 0.3;
+pub const SAY_COOLDOWN: Duration = Duration::from_secs(10);
+
+#[derive(Debug)]
+pub enum SpeechError {
+    Empty,
+    TooLong,
+    NotConnected,
+    Synthesis,
+    Cooldown(Duration),
+}
+
+lazy_static! {
+    static ref DUCKED: Mutex<HashMap<u64, usize>> = Mutex::new(HashMap::new());
+    static ref COOLDOWNS: Mutex<HashMap<u64, Instant>> = Mutex::new(HashMap::new());
+    static ref SPEECH_COUNT: AtomicUsize = AtomicUsize::new(0);
+}
+
+pub async fn say(ctx: &Context, guild_id: u64, text: &str) -> SpeechResult<()> {
+    // A vez é reservada junto com a verificação, então dois `!say` ao mesmo
+    // tempo não passam juntos.
+    let previous = {
+        let mut cooldowns = COOLDOWNS.lock().await;
+
+        if let Some(last_spoken) = cooldowns.get(&guild_id) {
+            let elapsed = last_spoken.elapsed();
+
+            if elapsed < SAY_COOLDOWN {
+                return Err(SpeechError::Cooldown(SAY_COOLDOWN - elapsed));
+            }
+        }
+
+        cooldowns.insert(guild_id, Instant::now())
+    };
+
+    if let Err(why) = speak(ctx, guild_id, text).await {
+        let mut cooldowns = COOLDOWNS.lock().await;
+
+        match previous {
+            Some(last_spoken) => cooldowns.insert(guild_id, last_spoken),
+            None => cooldowns.remove(&guild_id),
+        };
+
+        return Err(why);
+    }
+
+    Ok(())
+}
+
+pub async fn speak(ctx: &Context, guild_id: u64, text: &str) -> SpeechResult<()> {
+    let text = text.trim();
+
+    if text.is_empty() {
+        return Err(SpeechError::Empty);
+    }
+
+    if text.chars().count() > MAX_SPEECH_LENGTH {
+        return Err(SpeechError::TooLong);
+    }
+
+    let manager = songbird::get(ctx).await.unwrap();
+
+    let handler_lock = manager.get(guild_id).ok_or(SpeechError::NotConnected)?;
+
+    if handler_lock.lock().await.current_connection().is_none() {
+        return Err(SpeechError::NotConnected);
+    }
+
+    let path = synthesize(guild_id, text).await?;
+
+    let source = match songbird::ffmpeg(&path).await {
+        Ok(source) => source,
+        Err(_) => {
+            let _ = tokio::fs::remove_file(&path).await;
+
+            return Err(SpeechError::Synthesis);
+        }
+    };
+
+    let (track, track_handle) = create_player(source);
+
+    let _ = track_handle.add_event(
+        Event::Track(TrackEvent::End),
+        SpeechEndHandle { guild_id, path },
+    );
+
+    duck(guild_id).await;
+
+    handler_lock.lock().await.play(track);
+
+    Ok(())
+}
+
+pub async fn finish(guild_id: u64, path: &Path) {
+    let _ = tokio::fs::remove_file(path).await;
+
+    {
+        let mut ducked = DUCKED.lock().await;
+
+        let speakers = match ducked.get_mut(&guild_id) {
+            Some(speakers) => speakers,
+            None => return,
+        };
+
+        *speakers -= 1;
+
+        if *speakers > 0 {
+            return;
+        }
+
+        ducked.remove(&guild_id);
+    }
+
+    // A faixa pode ter mudado durante a fala, então o volume é reaplicado na
+    // atual em vez de restaurar o de quando a fala começou.
+    player::apply_volume(guild_id).await;
+}
+
+pub async fn is_ducked(guild_id: u64) -> bool {
+    DUCKED.lock().await.contains_key(&guild_id)
+}
+
+async fn duck(guild_id: u64) {
+    *DUCKED.lock().await.entry(guild_id).or_insert(0) += 1;
+
+    player::apply_volume(guild_id).await;
+}
+
+async fn synthesize(guild_id: u64, text: &str) -> SpeechResult<PathBuf> {
+    let count = SPEECH_COUNT.fetch_add(1, Ordering::Relaxed);
+    let path = std::env::temp_dir().join(format!("bender-tts-{guild_id}-{count}.wav"));
+
+    let mut child = Command::new(TTS_COMMAND)
+        .arg("-v")
+        .arg(TTS_VOICE)
+        .arg("-w")
+        .arg(&path)
+        .arg("--stdin")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|_| SpeechError::Synthesis)?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(text.as_bytes())
+            .await
+            .map_err(|_| SpeechError::Synthesis)?;
+    }
+
+    let status = child.wait().await.map_err(|_| SpeechError::Synthesis)?;
+
+    if !status.success() {
+        let _ = tokio::fs::remove_file(&path).await;
+
+        return Err(SpeechError::Synthesis);
+    }
+
+    Ok(path)
+}
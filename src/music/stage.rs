@@ -0,0 +1,71 @@
+use serenity::{
+    model::prelude::{ChannelId, ChannelType, GuildId},
+    prelude::Context,
+};
+
+use super::player::{self, PlayerError};
+use crate::settings;
+
+const MAX_TOPIC_LENGTH: usize = 120;
+pub const REQUESTED_TO_SPEAK: &str =
+    "Pedi para falar no palco, um moderador precisa aceitar o pedido.";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpeakerStatus {
+    NotStage,
+    Speaker,
+    Requested,
+}
+
+pub async fn become_speaker(
+    ctx: &Context,
+    channel_id: ChannelId,
+) -> Result<SpeakerStatus, PlayerError> {
+    let channel = match ctx.cache.guild_channel(channel_id) {
+        Some(channel) if channel.kind == ChannelType::Stage => channel,
+        _ => return Ok(SpeakerStatus::NotStage),
+    };
+
+    let speaking = channel
+        .edit_own_voice_state(&ctx.http, |v| v.suppress(false))
+        .await;
+
+    if speaking.is_ok() {
+        return Ok(SpeakerStatus::Speaker);
+    }
+
+    let requested = channel
+        .edit_own_voice_state(&ctx.http, |v| v.request_to_speak(true))
+        .await;
+
+    match requested {
+        Ok(_) => Ok(SpeakerStatus::Requested),
+        Err(_) => Err(PlayerError::StageNoPermission),
+    }
+}
+
+pub async fn update_topic(ctx: &Context, guild_id: GuildId, title: &str) {
+    if !settings::get(guild_id.0).await.stage_topic {
+        return;
+    }
+
+    let channel = match player::current_channel(ctx, guild_id).await {
+        Some(channel_id) => ctx.cache.guild_channel(channel_id),
+        None => return,
+    };
+
+    let channel = match channel {
+        Some(channel) if channel.kind == ChannelType::Stage => channel,
+        _ => return,
+    };
+
+    let topic = format!("Tocando: {title}")
+        .chars()
+        .take(MAX_TOPIC_LENGTH)
+        .collect::<String>();
+
+    // Só atualiza um palco já aberto, abrir um palco notifica o servidor todo.
+    let _ = channel
+        .edit_stage_instance(&ctx.http, |i| i.topic(&topic))
+        .await;
+}
@@ -0,0 +1,169 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::player::MediaInfo;
+
+pub const MAX_IMPORT_SIZE: u64 = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueueFormat {
+    Json,
+    M3u,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedTrack {
+    title: String,
+    artist: String,
+    duration: Option<u64>,
+    url: String,
+    source: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ExportedQueue {
+    tracks: Vec<ExportedTrack>,
+}
+
+impl QueueFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_lowercase().as_str() {
+            "" | "json" => Some(QueueFormat::Json),
+            "m3u" | "m3u8" => Some(QueueFormat::M3u),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            QueueFormat::Json => "json",
+            QueueFormat::M3u => "m3u",
+        }
+    }
+}
+
+pub fn export(tracks: &[MediaInfo], format: QueueFormat) -> String {
+    match format {
+        QueueFormat::Json => to_json(tracks),
+        QueueFormat::M3u => to_m3u(tracks),
+    }
+}
+
+fn to_json(tracks: &[MediaInfo]) -> String {
+    let queue = ExportedQueue {
+        tracks: tracks
+            .iter()
+            .map(|media_info| ExportedTrack {
+                title: media_info.title.clone(),
+                artist: media_info.artist.clone(),
+                duration: media_info.video_duration.map(|duration| duration.as_secs()),
+                url: media_info.url.clone(),
+                source: media_info.origin.name().to_string(),
+            })
+            .collect(),
+    };
+
+    serde_json::to_string_pretty(&queue).unwrap()
+}
+
+fn to_m3u(tracks: &[MediaInfo]) -> String {
+    let mut content = String::from("#EXTM3U\n");
+
+    for media_info in tracks {
+        let duration = media_info
+            .video_duration
+            .map(|duration| duration.as_secs() as i64)
+            .unwrap_or(-1);
+
+        content.push_str(&format!(
+            "#EXTINF:{duration},{} - {}\n{}\n",
+            media_info.artist.replace('\n', " "),
+            media_info.title.replace('\n', " "),
+            media_info.url
+        ));
+    }
+
+    content
+}
+
+// Aceita o JSON gerado pelo export, uma lista JSON de URLs ou objetos com
+// `url`, M3U e arquivos de texto com uma URL (ou busca) por linha.
+pub fn parse(content: &str) -> Vec<String> {
+    let content = content.trim_start_matches('\u{feff}').trim();
+
+    if content.starts_with('{') || content.starts_with('[') {
+        if let Ok(value) = serde_json::from_str::<Value>(content) {
+            return parse_json(&value);
+        }
+    }
+
+    content
+        .lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.to_string())
+        .collect()
+}
+
+fn parse_json(value: &Value) -> Vec<String> {
+    let entries = match value {
+        Value::Array(entries) => entries,
+        Value::Object(object) => match object.get("tracks") {
+            Some(Value::Array(entries)) => entries,
+            _ => return vec![],
+        },
+        _ => return vec![],
+    };
+
+    entries
+        .iter()
+        .filter_map(|entry| match entry {
+            Value::String(url) => Some(url.trim().to_string()),
+            Value::Object(object) => object
+                .get("url")
+                .and_then(|url| url.as_str())
+                .map(|url| url.trim().to_string()),
+            _ => None,
+        })
+        .filter(|url| !url.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_reads_exported_json() {
+        let content = r#"{"tracks": [{"title": "a", "url": " https://a "}, {"url": ""}, {"url": "https://b"}]}"#;
+
+        assert_eq!(parse(content), vec!["https://a", "https://b"]);
+    }
+
+    #[test]
+    fn parse_reads_json_lists() {
+        let content = r#"["https://a", {"url": "https://b"}, 3, {"title": "c"}]"#;
+
+        assert_eq!(parse(content), vec!["https://a", "https://b"]);
+    }
+
+    #[test]
+    fn parse_reads_m3u() {
+        let content =
+            "#EXTM3U\n#EXTINF:120,Artist - Song\nhttps://a\n\n#EXTINF:-1,Live\nhttps://b\n";
+
+        assert_eq!(parse(content), vec!["https://a", "https://b"]);
+    }
+
+    #[test]
+    fn parse_reads_one_query_per_line() {
+        let content = "\u{feff}  never gonna give you up \r\n\r\nhttps://a\r\n";
+
+        assert_eq!(parse(content), vec!["never gonna give you up", "https://a"]);
+    }
+
+    #[test]
+    fn parse_falls_back_to_lines_on_invalid_json() {
+        assert_eq!(parse("[not json"), vec!["[not json"]);
+    }
+}
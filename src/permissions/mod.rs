@@ -0,0 +1,639 @@
+use serde::{Deserialize, Serialize};
+use serenity::{
+    framework::standard::{
+        macros::{command, group},
+        Args, CommandGroup, CommandResult,
+    },
+    model::prelude::{
+        interaction::application_command::ApplicationCommandInteraction, ChannelId, GuildId,
+        Member, Message, Permissions,
+    },
+    prelude::Context,
+    utils::{parse_channel, parse_role, MessageBuilder},
+};
+
+use crate::backup::BACKUP_GROUP;
+use crate::chat::CHAT_GROUP;
+use crate::moderation::MODERATION_GROUP;
+use crate::music::MUSIC_GROUP;
+use crate::network::NETWORK_GROUP;
+use crate::settings;
+use crate::status::STATUS_GROUP;
+
+static GROUPS: [&CommandGroup; 7] = [
+    &CHAT_GROUP,
+    &MUSIC_GROUP,
+    &MODERATION_GROUP,
+    &NETWORK_GROUP,
+    &BACKUP_GROUP,
+    &STATUS_GROUP,
+    &ACCESS_GROUP,
+];
+
+#[group]
+#[commands(perms)]
+pub struct Access;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Overrides {
+    pub allowed_roles: Vec<u64>,
+    pub denied_roles: Vec<u64>,
+    pub allowed_channels: Vec<u64>,
+    pub denied_channels: Vec<u64>,
+}
+
+impl Overrides {
+    fn is_empty(&self) -> bool {
+        self.allowed_roles.is_empty()
+            && self.denied_roles.is_empty()
+            && self.allowed_channels.is_empty()
+            && self.denied_channels.is_empty()
+    }
+}
+
+#[derive(Debug)]
+pub enum PermissionError {
+    Channel,
+    Role,
+    Missing(Permissions),
+}
+
+impl std::fmt::Display for PermissionError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PermissionError::Channel => write!(f, "Este comando não pode ser usado neste canal."),
+            PermissionError::Role => write!(f, "Você não tem permissão para usar este comando."),
+            PermissionError::Missing(permissions) => write!(
+                f,
+                "Você precisa da permissão {} para usar este comando.",
+                permissions.get_permission_names().join(", ")
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Target {
+    Command(&'static str),
+    Group(&'static str),
+}
+
+impl Target {
+    fn parse(value: &str, group: bool) -> Option<Self> {
+        let value = value.to_lowercase();
+
+        if group {
+            return GROUPS
+                .iter()
+                .find(|g| g.name.to_lowercase() == value)
+                .map(|g| Target::Group(g.name));
+        }
+
+        canonical(&value).map(Target::Command)
+    }
+
+    fn key(&self) -> String {
+        match self {
+            Target::Command(name) => command_key(name),
+            Target::Group(name) => group_key(name),
+        }
+    }
+
+    fn name(&self) -> String {
+        match self {
+            Target::Command(name) => format!("`!{name}`"),
+            Target::Group(name) => format!("grupo {name}"),
+        }
+    }
+}
+
+// Permissões padrão do Discord exigidas por cada comando, valem quando nenhuma
+// sobrescrita do servidor libera o cargo.
+pub fn default_permissions(command: &str) -> Permissions {
+    match command {
+        "clear" | "transcript" => Permissions::MANAGE_MESSAGES,
+        "warn" | "timeout" | "mute" | "unmute" | "infractions" | "reason" => {
+            Permissions::MODERATE_MEMBERS
+        }
+        "kick" => Permissions::KICK_MEMBERS,
+        "ban" | "tempban" | "unban" => Permissions::BAN_MEMBERS,
+        "lock" | "unlock" | "lockdown" | "slowmode" => Permissions::MANAGE_CHANNELS,
+        "backup" | "clearscan" | "transcripts" | "modlog" | "automod" | "blocklist"
+        | "muterole" | "perms" | "source" | "limits" | "dj" | "announce" | "247" | "stagetopic"
+        | "fairqueue" => Permissions::MANAGE_GUILD,
+        _ => Permissions::empty(),
+    }
+}
+
+fn command_key(name: &str) -> String {
+    format!("command:{name}")
+}
+
+fn group_key(name: &str) -> String {
+    format!("group:{}", name.to_lowercase())
+}
+
+// Resolve aliases e nomes de comandos de barra para o nome principal do
+// comando de prefixo, que é o usado nas sobrescritas.
+fn canonical(name: &str) -> Option<&'static str> {
+    GROUPS
+        .iter()
+        .flat_map(|g| g.options.commands.iter())
+        .find(|c| c.options.names.contains(&name))
+        .map(|c| c.options.names[0])
+}
+
+fn group_of(command: &str) -> Option<&'static str> {
+    GROUPS
+        .iter()
+        .find(|g| {
+            g.options
+                .commands
+                .iter()
+                .any(|c| c.options.names[0] == command)
+        })
+        .map(|g| g.name)
+}
+
+pub async fn check_message(
+    ctx: &Context,
+    msg: &Message,
+    command: &str,
+) -> Result<(), PermissionError> {
+    let guild_id = match msg.guild_id {
+        Some(guild_id) => guild_id,
+        None => return Ok(()),
+    };
+
+    let member = match msg.member(ctx).await {
+        Ok(member) => member,
+        Err(_) => return Err(PermissionError::Role),
+    };
+
+    let permissions = ctx
+        .cache
+        .guild(guild_id)
+        .and_then(|guild| {
+            let channel = guild
+                .channels
+                .get(&msg.channel_id)
+                .and_then(|channel| channel.clone().guild())
+                .or_else(|| {
+                    guild
+                        .threads
+                        .iter()
+                        .find(|t| t.id == msg.channel_id)
+                        .cloned()
+                })?;
+
+            guild.user_permissions_in(&channel, &member).ok()
+        })
+        .or_else(|| member.permissions(&ctx.cache).ok())
+        .unwrap_or_else(Permissions::empty);
+
+    check(ctx, guild_id, msg.channel_id, &member, permissions, command).await
+}
+
+pub async fn check_interaction(
+    ctx: &Context,
+    command: &ApplicationCommandInteraction,
+) -> Result<(), PermissionError> {
+    let (guild_id, member) = match (command.guild_id, &command.member) {
+        (Some(guild_id), Some(member)) => (guild_id, member),
+        _ => return Ok(()),
+    };
+
+    let permissions = member.permissions.unwrap_or_else(Permissions::empty);
+
+    check(
+        ctx,
+        guild_id,
+        command.channel_id,
+        member,
+        permissions,
+        &command.data.name,
+    )
+    .await
+}
+
+async fn check(
+    ctx: &Context,
+    guild_id: GuildId,
+    channel_id: ChannelId,
+    member: &Member,
+    permissions: Permissions,
+    command: &str,
+) -> Result<(), PermissionError> {
+    let is_owner = ctx.cache.guild_field(guild_id, |g| g.owner_id) == Some(member.user.id);
+
+    if is_owner || permissions.administrator() {
+        return Ok(());
+    }
+
+    let settings = settings::get(guild_id.0).await;
+
+    let command = canonical(command).unwrap_or(command);
+
+    let mut targets = vec![command_key(command)];
+
+    if let Some(group) = group_of(command) {
+        targets.push(group_key(group));
+    }
+
+    let overrides = targets
+        .iter()
+        .filter_map(|key| settings.permissions.get(key))
+        .collect::<Vec<&Overrides>>();
+
+    let mut roles = member.roles.iter().map(|role| role.0).collect::<Vec<u64>>();
+
+    roles.push(guild_id.0);
+
+    resolve(
+        &overrides,
+        channel_id.0,
+        &roles,
+        permissions,
+        default_permissions(command),
+    )
+}
+
+// Todos os bloqueios, do comando e do grupo, valem antes de qualquer
+// liberação: canais bloqueados sempre impedem o uso, um cargo bloqueado vence
+// um liberado e só então um cargo liberado dispensa as permissões padrão.
+fn resolve(
+    overrides: &[&Overrides],
+    channel_id: u64,
+    roles: &[u64],
+    permissions: Permissions,
+    required: Permissions,
+) -> Result<(), PermissionError> {
+    for overrides in overrides.iter() {
+        if overrides.denied_channels.contains(&channel_id) {
+            return Err(PermissionError::Channel);
+        }
+
+        if !overrides.allowed_channels.is_empty()
+            && !overrides.allowed_channels.contains(&channel_id)
+        {
+            return Err(PermissionError::Channel);
+        }
+    }
+
+    let has_any = |list: &[u64]| roles.iter().any(|role| list.contains(role));
+
+    if overrides
+        .iter()
+        .any(|overrides| has_any(&overrides.denied_roles))
+    {
+        return Err(PermissionError::Role);
+    }
+
+    if overrides
+        .iter()
+        .any(|overrides| has_any(&overrides.allowed_roles))
+    {
+        return Ok(());
+    }
+
+    if !permissions.contains(required) {
+        return Err(PermissionError::Missing(required));
+    }
+
+    Ok(())
+}
+
+#[command]
+#[only_in(guilds)]
+pub async fn perms(ctx: &Context, msg: &Message, mut args: Args) -> CommandResult {
+    let guild_id = msg.guild_id.unwrap().0;
+
+    let group = args.current() == Some("--group");
+
+    if group {
+        args.advance();
+    }
+
+    let target = match args.single::<String>() {
+        Ok(value) => Target::parse(&value, group),
+        Err(_) => {
+            msg.channel_id
+                .say(&ctx.http, overview(&settings::get(guild_id).await))
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let target = match target {
+        Some(target) => target,
+        None => {
+            msg.reply(&ctx.http, "Comando ou grupo não encontrado.")
+                .await?;
+
+            return Ok(());
+        }
+    };
+
+    let action = args.single::<String>().unwrap_or_default().to_lowercase();
+    let value = args.rest().trim().to_lowercase();
+
+    let mut overrides = settings::get(guild_id)
+        .await
+        .permissions
+        .get(&target.key())
+        .cloned()
+        .unwrap_or_default();
+
+    let role_id = match value.as_str() {
+        "everyone" => Some(guild_id),
+        value => parse_role(value),
+    };
+    let channel_id = parse_channel(&value);
+
+    let content = match (action.as_str(), role_id, channel_id) {
+        ("", _, _) => {
+            msg.channel_id
+                .say(&ctx.http, describe(&target, &overrides))
+                .await?;
+
+            return Ok(());
+        }
+        ("reset", None, None) => {
+            overrides = Overrides::default();
+
+            format!("Permissões de {} restauradas para o padrão.", target.name())
+        }
+        ("allow" | "deny" | "reset", Some(role_id), _) => {
+            overrides.allowed_roles.retain(|id| *id != role_id);
+            overrides.denied_roles.retain(|id| *id != role_id);
+
+            match action.as_str() {
+                "allow" => overrides.allowed_roles.push(role_id),
+                "deny" => overrides.denied_roles.push(role_id),
+                _ => {}
+            }
+
+            format!("Permissões de {} atualizadas.", target.name())
+        }
+        ("allow" | "deny" | "reset", None, Some(channel_id)) => {
+            overrides.allowed_channels.retain(|id| *id != channel_id);
+            overrides.denied_channels.retain(|id| *id != channel_id);
+
+            match action.as_str() {
+                "allow" => overrides.allowed_channels.push(channel_id),
+                "deny" => overrides.denied_channels.push(channel_id),
+                _ => {}
+            }
+
+            format!("Permissões de {} atualizadas.", target.name())
+        }
+        _ => {
+            msg.reply(
+                &ctx.http,
+                "Use `!perms [--group] <alvo> [allow|deny|reset] [@cargo|#canal|everyone]`.",
+            )
+            .await?;
+
+            return Ok(());
+        }
+    };
+
+    let key = target.key();
+
+    let updated = settings::update(guild_id, |s| {
+        if overrides.is_empty() {
+            s.permissions.remove(&key);
+        } else {
+            s.permissions.insert(key, overrides);
+        }
+    })
+    .await;
+
+    let content = match updated {
+        Ok(_) => content,
+        Err(_) => "Não foi possível salvar a configuração.".to_string(),
+    };
+
+    msg.reply(&ctx.http, content).await?;
+
+    Ok(())
+}
+
+fn describe(target: &Target, overrides: &Overrides) -> String {
+    let mut content = MessageBuilder::new();
+
+    content.push_bold_line(format!("Permissões de {}", target.name()));
+
+    if let Target::Command(name) = target {
+        let required = default_permissions(name);
+
+        let required = match required.is_empty() {
+            true => "nenhuma".to_string(),
+            false => required.get_permission_names().join(", "),
+        };
+
+        content.push_line(format!("Padrão: {required}"));
+    }
+
+    let roles = |ids: &[u64]| {
+        ids.iter()
+            .map(|id| format!("<@&{id}>"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    let channels = |ids: &[u64]| {
+        ids.iter()
+            .map(|id| format!("<#{id}>"))
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+
+    let lines = [
+        ("Cargos liberados", roles(&overrides.allowed_roles)),
+        ("Cargos bloqueados", roles(&overrides.denied_roles)),
+        ("Canais permitidos", channels(&overrides.allowed_channels)),
+        ("Canais bloqueados", channels(&overrides.denied_channels)),
+    ];
+
+    for (name, value) in lines {
+        if !value.is_empty() {
+            content.push_line(format!("{name}: {value}"));
+        }
+    }
+
+    if overrides.is_empty() {
+        content.push_line("Nenhuma sobrescrita definida.");
+    }
+
+    content.build()
+}
+
+fn overview(settings: &settings::GuildSettings) -> String {
+    if settings.permissions.is_empty() {
+        return "Nenhuma sobrescrita de permissões, todos os comandos usam o padrão. Use `!perms [--group] <alvo>` para ver ou alterar.".to_string();
+    }
+
+    let mut content = MessageBuilder::new();
+
+    content.push_bold_line("Sobrescritas de permissões");
+
+    let mut keys = settings.permissions.keys().collect::<Vec<&String>>();
+
+    keys.sort();
+
+    for key in keys {
+        content.push_line(format!("`{key}`"));
+    }
+
+    content.build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHANNEL: u64 = 10;
+    const OTHER_CHANNEL: u64 = 11;
+    const EVERYONE: u64 = 1;
+    const DJ: u64 = 2;
+    const MUTED: u64 = 3;
+
+    fn roles(allowed: &[u64], denied: &[u64]) -> Overrides {
+        Overrides {
+            allowed_roles: allowed.to_vec(),
+            denied_roles: denied.to_vec(),
+            ..Overrides::default()
+        }
+    }
+
+    fn channels(allowed: &[u64], denied: &[u64]) -> Overrides {
+        Overrides {
+            allowed_channels: allowed.to_vec(),
+            denied_channels: denied.to_vec(),
+            ..Overrides::default()
+        }
+    }
+
+    #[test]
+    fn group_deny_beats_command_allow() {
+        let command = roles(&[DJ], &[]);
+        let group = roles(&[], &[MUTED]);
+
+        assert!(matches!(
+            resolve(
+                &[&command, &group],
+                CHANNEL,
+                &[EVERYONE, DJ, MUTED],
+                Permissions::empty(),
+                Permissions::MANAGE_GUILD
+            ),
+            Err(PermissionError::Role)
+        ));
+    }
+
+    #[test]
+    fn command_deny_beats_group_allow() {
+        let command = roles(&[], &[EVERYONE]);
+        let group = roles(&[DJ], &[]);
+
+        assert!(matches!(
+            resolve(
+                &[&command, &group],
+                CHANNEL,
+                &[EVERYONE, DJ],
+                Permissions::MANAGE_GUILD,
+                Permissions::empty()
+            ),
+            Err(PermissionError::Role)
+        ));
+    }
+
+    #[test]
+    fn allowed_role_skips_default_permissions() {
+        let command = roles(&[DJ], &[]);
+
+        assert!(resolve(
+            &[&command],
+            CHANNEL,
+            &[EVERYONE, DJ],
+            Permissions::empty(),
+            Permissions::MANAGE_GUILD
+        )
+        .is_ok());
+        assert!(matches!(
+            resolve(
+                &[&command],
+                CHANNEL,
+                &[EVERYONE],
+                Permissions::empty(),
+                Permissions::MANAGE_GUILD
+            ),
+            Err(PermissionError::Missing(_))
+        ));
+    }
+
+    #[test]
+    fn denied_channel_beats_allowed_role() {
+        let command = roles(&[DJ], &[]);
+        let group = channels(&[], &[CHANNEL]);
+
+        assert!(matches!(
+            resolve(
+                &[&command, &group],
+                CHANNEL,
+                &[EVERYONE, DJ],
+                Permissions::empty(),
+                Permissions::empty()
+            ),
+            Err(PermissionError::Channel)
+        ));
+    }
+
+    #[test]
+    fn allowed_channels_restrict_to_the_list() {
+        let group = channels(&[OTHER_CHANNEL], &[]);
+
+        assert!(matches!(
+            resolve(
+                &[&group],
+                CHANNEL,
+                &[EVERYONE],
+                Permissions::empty(),
+                Permissions::empty()
+            ),
+            Err(PermissionError::Channel)
+        ));
+        assert!(resolve(
+            &[&group],
+            OTHER_CHANNEL,
+            &[EVERYONE],
+            Permissions::empty(),
+            Permissions::empty()
+        )
+        .is_ok());
+    }
+
+    #[test]
+    fn default_permissions_apply_without_overrides() {
+        assert!(resolve(
+            &[],
+            CHANNEL,
+            &[EVERYONE],
+            Permissions::MANAGE_GUILD,
+            Permissions::MANAGE_GUILD
+        )
+        .is_ok());
+        assert!(matches!(
+            resolve(
+                &[],
+                CHANNEL,
+                &[EVERYONE],
+                Permissions::SEND_MESSAGES,
+                Permissions::MANAGE_GUILD
+            ),
+            Err(PermissionError::Missing(required)) if required == Permissions::MANAGE_GUILD
+        ));
+    }
+}
@@ -0,0 +1,340 @@
+use std::{sync::Arc, time::Duration};
+
+use chrono::Utc;
+use redis::{AsyncCommands, RedisResult};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    http::Http,
+    model::prelude::{ChannelId, GuildId, RoleId, UserId},
+    prelude::Mentionable,
+};
+
+use crate::database;
+use crate::moderation::{self, LogEntry};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+const POLL_BATCH: isize = 50;
+const MAX_ATTEMPTS: u32 = 5;
+const RETRY_DELAY: i64 = 60;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Job {
+    Unban {
+        guild_id: u64,
+        user_id: u64,
+        channel_id: u64,
+    },
+    Unmute {
+        guild_id: u64,
+        user_id: u64,
+        role_id: u64,
+        channel_id: u64,
+    },
+    Reminder {
+        user_id: u64,
+        channel_id: u64,
+        content: String,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScheduledJob {
+    job: Job,
+    due_at: i64,
+    attempts: u32,
+}
+
+pub fn unban_id(guild_id: u64, user_id: u64) -> String {
+    format!("unban:{guild_id}:{user_id}")
+}
+
+pub fn unmute_id(guild_id: u64, user_id: u64) -> String {
+    format!("unmute:{guild_id}:{user_id}")
+}
+
+pub async fn reminder_id(user_id: u64) -> RedisResult<String> {
+    let mut connection = database::connection().await?;
+
+    let count: u64 = connection.incr(database::key("jobs:count"), 1).await?;
+
+    Ok(format!("reminder:{user_id}:{count}"))
+}
+
+// Agendar de novo com o mesmo id substitui o job anterior.
+pub async fn schedule(id: &str, job: Job, due_at: i64) -> RedisResult<()> {
+    let scheduled = ScheduledJob {
+        job,
+        due_at,
+        attempts: 0,
+    };
+
+    write(id, &scheduled).await
+}
+
+pub async fn cancel(id: &str) -> RedisResult<()> {
+    let mut connection = database::connection().await?;
+
+    let _: () = connection.zrem(database::key("jobs"), id).await?;
+    let _: () = connection.hdel(database::key("jobs:data"), id).await?;
+
+    Ok(())
+}
+
+pub fn start(http: Arc<Http>) {
+    tokio::spawn(async move {
+        loop {
+            let _ = poll(&http).await;
+
+            tokio::time::sleep(POLL_INTERVAL).await;
+        }
+    });
+}
+
+// Um job só é removido depois de executado com sucesso, então pode rodar mais
+// de uma vez se o bot cair no meio; os handlers precisam tolerar isso.
+async fn poll(http: &Http) -> RedisResult<()> {
+    let mut connection = database::connection().await?;
+
+    let ids: Vec<String> = connection
+        .zrangebyscore_limit(
+            database::key("jobs"),
+            "-inf",
+            Utc::now().timestamp(),
+            0,
+            POLL_BATCH,
+        )
+        .await?;
+
+    for id in ids {
+        let value: Option<String> = connection.hget(database::key("jobs:data"), &id).await?;
+
+        let mut scheduled =
+            match value.and_then(|value| serde_json::from_str::<ScheduledJob>(&value).ok()) {
+                Some(scheduled) => scheduled,
+                None => {
+                    cancel(&id).await?;
+
+                    continue;
+                }
+            };
+
+        if run(http, &scheduled.job).await.is_ok() {
+            cancel(&id).await?;
+
+            continue;
+        }
+
+        if !reschedule(&mut scheduled, Utc::now().timestamp()) {
+            cancel(&id).await?;
+
+            continue;
+        }
+
+        write(&id, &scheduled).await?;
+    }
+
+    Ok(())
+}
+
+// Cada falha espera um pouco mais; devolve `false` quando as tentativas acabam.
+fn reschedule(scheduled: &mut ScheduledJob, now: i64) -> bool {
+    scheduled.attempts += 1;
+
+    if scheduled.attempts >= MAX_ATTEMPTS {
+        return false;
+    }
+
+    scheduled.due_at = now + RETRY_DELAY * scheduled.attempts as i64;
+
+    true
+}
+
+async fn write(id: &str, scheduled: &ScheduledJob) -> RedisResult<()> {
+    let value = serde_json::to_string(scheduled).unwrap();
+
+    let mut connection = database::connection().await?;
+
+    let _: () = connection
+        .hset(database::key("jobs:data"), id, value)
+        .await?;
+    let _: () = connection
+        .zadd(database::key("jobs"), id, scheduled.due_at)
+        .await?;
+
+    Ok(())
+}
+
+async fn run(http: &Http, job: &Job) -> serenity::Result<()> {
+    match job {
+        Job::Unban {
+            guild_id,
+            user_id,
+            channel_id,
+        } => {
+            match GuildId(*guild_id).unban(http, UserId(*user_id)).await {
+                Err(why) if !is_not_found(&why) => return Err(why),
+                Err(_) => return Ok(()),
+                Ok(_) => {}
+            }
+
+            moderation::deliver(
+                http,
+                GuildId(*guild_id),
+                ChannelId(*channel_id),
+                LogEntry::new("Banimento expirado", http.get_current_user().await?.id)
+                    .target(UserId(*user_id).mention()),
+            )
+            .await;
+        }
+        Job::Unmute {
+            guild_id,
+            user_id,
+            role_id,
+            channel_id,
+        } => {
+            let member = match GuildId(*guild_id).member(http, UserId(*user_id)).await {
+                Ok(member) => member,
+                Err(why) if is_not_found(&why) => return Ok(()),
+                Err(why) => return Err(why),
+            };
+
+            if !member.roles.contains(&RoleId(*role_id)) {
+                return Ok(());
+            }
+
+            http.remove_member_role(
+                *guild_id,
+                *user_id,
+                *role_id,
+                Some("Silenciamento expirado"),
+            )
+            .await?;
+
+            moderation::deliver(
+                http,
+                GuildId(*guild_id),
+                ChannelId(*channel_id),
+                LogEntry::new("Silenciamento expirado", http.get_current_user().await?.id)
+                    .target(UserId(*user_id).mention()),
+            )
+            .await;
+        }
+        Job::Reminder {
+            user_id,
+            channel_id,
+            content,
+        } => {
+            let content = format!("{}, lembrete: {content}", UserId(*user_id).mention());
+
+            match ChannelId(*channel_id).say(http, &content).await {
+                Err(why) if !is_not_found(&why) => return Err(why),
+                Err(_) => {
+                    let channel = UserId(*user_id).create_dm_channel(http).await?;
+
+                    channel.say(http, &content).await?;
+                }
+                Ok(_) => {}
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn is_not_found(error: &serenity::Error) -> bool {
+    match error {
+        serenity::Error::Http(error) => {
+            error.status_code().map(|status| status.as_u16()) == Some(404)
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn jobs_round_trip_through_json() {
+        let jobs = [
+            Job::Unban {
+                guild_id: 1,
+                user_id: 2,
+                channel_id: 3,
+            },
+            Job::Unmute {
+                guild_id: 1,
+                user_id: 2,
+                role_id: 4,
+                channel_id: 3,
+            },
+            Job::Reminder {
+                user_id: 2,
+                channel_id: 3,
+                content: "beber água".to_string(),
+            },
+        ];
+
+        for job in jobs {
+            let value = serde_json::to_string(&job).unwrap();
+            let parsed = serde_json::from_str::<Job>(&value).unwrap();
+
+            assert_eq!(serde_json::to_string(&parsed).unwrap(), value);
+        }
+    }
+
+    #[test]
+    fn stored_jobs_keep_their_format() {
+        let scheduled = serde_json::from_str::<ScheduledJob>(
+            r#"{"job":{"type":"Unban","guild_id":1,"user_id":2,"channel_id":3},"due_at":100,"attempts":2}"#,
+        )
+        .unwrap();
+
+        assert!(matches!(
+            scheduled.job,
+            Job::Unban {
+                guild_id: 1,
+                user_id: 2,
+                channel_id: 3
+            }
+        ));
+        assert_eq!(scheduled.due_at, 100);
+        assert_eq!(scheduled.attempts, 2);
+    }
+
+    #[test]
+    fn reschedule_waits_longer_after_each_failure() {
+        let mut scheduled = ScheduledJob {
+            job: Job::Reminder {
+                user_id: 1,
+                channel_id: 2,
+                content: String::new(),
+            },
+            due_at: 0,
+            attempts: 0,
+        };
+
+        assert!(reschedule(&mut scheduled, 1000));
+        assert_eq!(scheduled.due_at, 1000 + RETRY_DELAY);
+
+        assert!(reschedule(&mut scheduled, 2000));
+        assert_eq!(scheduled.due_at, 2000 + RETRY_DELAY * 2);
+    }
+
+    #[test]
+    fn reschedule_gives_up_after_max_attempts() {
+        let mut scheduled = ScheduledJob {
+            job: Job::Reminder {
+                user_id: 1,
+                channel_id: 2,
+                content: String::new(),
+            },
+            due_at: 0,
+            attempts: MAX_ATTEMPTS - 1,
+        };
+
+        assert!(!reschedule(&mut scheduled, 1000));
+        assert_eq!(scheduled.due_at, 0);
+    }
+}
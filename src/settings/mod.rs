@@ -0,0 +1,117 @@
+use std::{collections::HashMap, sync::Arc};
+
+use lazy_static::lazy_static;
+use redis::{AsyncCommands, ErrorKind, RedisError, RedisResult};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::chat::{CLEAR_SCAN_LIMIT, MAX_CLEAR_SCAN_LIMIT};
+use crate::database;
+use crate::moderation::AutomodSettings;
+use crate::music::playlist::{MAX_PAGE_SIZE, MAX_PLAYLIST_LIMIT, PAGE_SIZE, PLAYLIST_LIMIT};
+use crate::music::query::SearchSource;
+use crate::permissions::Overrides;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GuildSettings {
+    pub search_source: SearchSource,
+    pub dj_role_id: Option<u64>,
+    pub max_entries_per_user: Option<usize>,
+    pub max_track_duration: Option<u64>,
+    pub max_queue_duration: Option<u64>,
+    pub block_live: bool,
+    pub playlist_limit: Option<usize>,
+    pub page_size: Option<usize>,
+    pub crossfade: u64,
+    pub announcements: bool,
+    pub stay_connected: bool,
+    pub voice_channel_id: Option<u64>,
+    pub stage_topic: bool,
+    pub fair_queue: bool,
+    pub clear_scan_limit: Option<usize>,
+    pub save_transcripts: bool,
+    pub mod_log_channel_id: Option<u64>,
+    pub mute_role_id: Option<u64>,
+    pub automod: AutomodSettings,
+    pub permissions: HashMap<String, Overrides>,
+}
+
+impl GuildSettings {
+    pub fn playlist_limit(&self) -> usize {
+        self.playlist_limit
+            .unwrap_or(PLAYLIST_LIMIT)
+            .clamp(1, MAX_PLAYLIST_LIMIT)
+    }
+
+    pub fn page_size(&self) -> usize {
+        self.page_size.unwrap_or(PAGE_SIZE).clamp(1, MAX_PAGE_SIZE)
+    }
+
+    pub fn clear_scan_limit(&self) -> usize {
+        self.clear_scan_limit
+            .unwrap_or(CLEAR_SCAN_LIMIT)
+            .clamp(100, MAX_CLEAR_SCAN_LIMIT)
+    }
+}
+
+lazy_static! {
+    static ref SETTINGS: Mutex<HashMap<u64, GuildSettings>> = Mutex::new(HashMap::new());
+    static ref UPDATES: Mutex<HashMap<u64, Arc<Mutex<()>>>> = Mutex::new(HashMap::new());
+}
+
+pub async fn get(guild_id: u64) -> GuildSettings {
+    if let Some(settings) = SETTINGS.lock().await.get(&guild_id) {
+        return settings.clone();
+    }
+
+    // Uma falha no Redis não pode ficar em cache, senão o próximo update
+    // gravaria os padrões por cima da configuração salva.
+    let settings = match load(guild_id).await {
+        Ok(settings) => settings,
+        Err(_) => return GuildSettings::default(),
+    };
+
+    SETTINGS.lock().await.insert(guild_id, settings.clone());
+
+    settings
+}
+
+pub async fn update<F>(guild_id: u64, change: F) -> RedisResult<GuildSettings>
+where
+    F: FnOnce(&mut GuildSettings),
+{
+    let lock = UPDATES.lock().await.entry(guild_id).or_default().clone();
+    let _guard = lock.lock().await;
+
+    let mut settings = load(guild_id).await?;
+
+    change(&mut settings);
+
+    let value = serde_json::to_string(&settings).unwrap();
+
+    let mut connection = database::connection().await?;
+
+    let _: () = connection
+        .set(database::guild_key(guild_id, "settings"), value)
+        .await?;
+
+    SETTINGS.lock().await.insert(guild_id, settings.clone());
+
+    Ok(settings)
+}
+
+async fn load(guild_id: u64) -> RedisResult<GuildSettings> {
+    let mut connection = database::connection().await?;
+
+    let value: Option<String> = connection
+        .get(database::guild_key(guild_id, "settings"))
+        .await?;
+
+    match value {
+        Some(value) => serde_json::from_str(&value).map_err(|_| {
+            RedisError::from((ErrorKind::TypeError, "Configuração inválida no Redis."))
+        }),
+        None => Ok(GuildSettings::default()),
+    }
+}